@@ -1,4 +1,4 @@
-use crate::{hittable::{HitRecord, Hittable}, interval::Interval, vec3::Point3};
+use crate::{interval::Interval, ray::Ray, vec3::{Point3, Vec3}};
 
 #[derive(Default, Clone, Copy)]
 pub struct AABB {
@@ -40,6 +40,16 @@ impl AABB {
         }
     }
 
+    // the smallest box around all the points
+    pub fn enclosing(points: &[Point3]) -> AABB {
+        points.iter().fold(AABB::from_points(points[0], points[0]), |bounds, &p| AABB::from_sub_aabbs(bounds, AABB::from_points(p, p)))
+    }
+
+    // grown by delta along every axis, so that flat boxes still have some thickness to hit
+    pub fn padded(&self, delta: f64) -> AABB {
+        AABB::new(self.x.expand(delta), self.y.expand(delta), self.z.expand(delta))
+    }
+
     pub fn axis_inteval(&self, n: i32) -> Interval {
         match n {
             0 => self.x,
@@ -48,40 +58,69 @@ impl AABB {
             _ => panic!("Invalid axis")
         }
     }
-}
 
-impl Hittable for AABB {
-    fn hit(&self, ray: &crate::ray::Ray, interval: Interval) -> Option<HitRecord> {
-        for i in 0..4 {
-            let axis_interval = Self::axis_inteval(&self, i);
-            let adinv = 1.0 / ray.direction().e()[i as usize];
-
-            let t0 = (axis_interval.min - ray.origin().e()[i as usize]) * adinv;
-            let t1 = (axis_interval.max - ray.origin().e()[i as usize]) * adinv;
-
-            let mut result_interval = Interval::new(interval.min, interval.max);
-            if t0 < t1 {
-                if t0 > interval.min {result_interval.min = t0}
-                if t1 < interval.max {result_interval.max = t1}
-            } else {
-                if t1 > interval.min {result_interval.min = t1}
-                if t0 < interval.max {result_interval.max = t0}
-            }
+    pub fn min(&self) -> Point3 {
+        Point3::new(self.x.min, self.y.min, self.z.min)
+    }
 
-            if result_interval.max <= result_interval.min {
-                return None;
-            }
+    pub fn max(&self) -> Point3 {
+        Point3::new(self.x.max, self.y.max, self.z.max)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        0.5 * (self.min() + self.max())
+    }
+
+    pub fn diagonal(&self) -> Vec3 {
+        self.max() - self.min()
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        let (min, max) = (self.min(), self.max());
+        [
+            Point3::new(min.x(), min.y(), min.z()), Point3::new(max.x(), min.y(), min.z()),
+            Point3::new(min.x(), max.y(), min.z()), Point3::new(max.x(), max.y(), min.z()),
+            Point3::new(min.x(), min.y(), max.z()), Point3::new(max.x(), min.y(), max.z()),
+            Point3::new(min.x(), max.y(), max.z()), Point3::new(max.x(), max.y(), max.z()),
+        ]
+    }
+
+    pub fn longest_axis(&self) -> i32 {
+        let d = self.diagonal();
+        if d.x() > d.y() && d.x() > d.z() {
+            0
+        } else if d.y() > d.z() {
+            1
+        } else {
+            2
         }
+    }
+
+    pub fn contains(&self, p: Point3) -> bool {
+        self.x.contains(p.x()) && self.y.contains(p.y()) && self.z.contains(p.z())
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        let origin = [ray.origin().x(), ray.origin().y(), ray.origin().z()];
+        let direction = [ray.direction().x(), ray.direction().y(), ray.direction().z()];
 
+        let mut result_interval = Interval::new(t_min, t_max);
+        for i in 0..3 {
+            let axis_interval = self.axis_inteval(i);
+            let adinv = 1.0 / direction[i as usize];
 
-        let rec = HitRecord {
-            t: Default::default(),
-            p: Default::default(),
-            mat: None,
-            normal: Default::default(),
-            front_face: Default::default(),
-        };
+            let t0 = (axis_interval.min - origin[i as usize]) * adinv;
+            let t1 = (axis_interval.max - origin[i as usize]) * adinv;
 
-        Some(rec)
+            let (t0, t1) = if t0 < t1 {(t0, t1)} else {(t1, t0)};
+            if t0 > result_interval.min {result_interval.min = t0}
+            if t1 < result_interval.max {result_interval.max = t1}
+
+            if result_interval.max <= result_interval.min {
+                return false;
+            }
+        }
+
+        true
     }
-}
\ No newline at end of file
+}
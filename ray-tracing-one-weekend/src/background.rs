@@ -0,0 +1,12 @@
+use crate::{color::Color, vec3::Vec3};
+
+// what a ray sees when it escapes the scene
+pub trait Background: Send + Sync {
+    fn color(&self, direction: Vec3) -> Color;
+}
+
+impl Background for Color {
+    fn color(&self, _direction: Vec3) -> Color {
+        *self
+    }
+}
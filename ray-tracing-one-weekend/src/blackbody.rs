@@ -0,0 +1,136 @@
+use crate::{color::Color, texture::Texture, vec3::Point3};
+
+const PLANCK: f64 = 6.62607015e-34;
+const BOLTZMANN: f64 = 1.380649e-23;
+const SPEED_OF_LIGHT: f64 = 299792458.0;
+const STEFAN_BOLTZMANN: f64 = 5.670374419e-8;
+// lumens per watt of light at the peak of the photopic response
+const MAX_LUMINOUS_EFFICACY: f64 = 683.0;
+
+// visible range and step (nm) used to integrate spectra against the colour matching functions
+const LAMBDA_MIN: f64 = 360.0;
+const LAMBDA_MAX: f64 = 830.0;
+const LAMBDA_STEP: f64 = 5.0;
+
+// Planck's law: spectral radiance in W / (sr m^2 nm) at a wavelength in nanometres
+pub fn planck(wavelength: f64, temperature: f64) -> f64 {
+    let lambda = wavelength * 1e-9;
+    let c1 = 2.0 * PLANCK * SPEED_OF_LIGHT * SPEED_OF_LIGHT;
+    let c2 = PLANCK * SPEED_OF_LIGHT / BOLTZMANN;
+
+    c1 / (lambda.powi(5) * (f64::exp(c2 / (lambda * temperature)) - 1.0)) * 1e-9
+}
+
+// Wyman, Sloan and Shirley's multi-lobe fit of the CIE 1931 colour matching functions
+pub fn cie_xyz(wavelength: f64) -> (f64, f64, f64) {
+    let lobe = |mu: f64, sigma_low: f64, sigma_high: f64| {
+        let t = (wavelength - mu) / if wavelength < mu {sigma_low} else {sigma_high};
+        f64::exp(-0.5 * t * t)
+    };
+
+    let x = 1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7) - 0.065 * lobe(501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8);
+
+    (x, y, z)
+}
+
+// XYZ of a blackbody's spectral radiance, Y being its luminance divided by 683 lm/W
+pub fn blackbody_xyz(temperature: f64) -> (f64, f64, f64) {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    let mut wavelength = LAMBDA_MIN;
+    while wavelength <= LAMBDA_MAX {
+        let radiance = planck(wavelength, temperature);
+        let (cx, cy, cz) = cie_xyz(wavelength);
+        x += radiance * cx * LAMBDA_STEP;
+        y += radiance * cy * LAMBDA_STEP;
+        z += radiance * cz * LAMBDA_STEP;
+        wavelength += LAMBDA_STEP;
+    }
+
+    (x, y, z)
+}
+
+// linear sRGB colour of a blackbody, normalised to unit luminance
+pub fn blackbody(temperature: f64) -> Color {
+    if temperature <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let (x, y, z) = blackbody_xyz(temperature);
+    // very cold blackbodies emit too little visible light for y to be representable
+    if y <= 0.0 || !y.is_finite() {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+    let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+    let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+
+    // very red blackbodies fall outside the gamut, so clip rather than emit negative light
+    Color::new(f64::max(r, 0.0), f64::max(g, 0.0), f64::max(b, 0.0)) / y
+}
+
+// how bright a light is: radiant watts over the whole blackbody spectrum, or lumens of visible light
+#[derive(Clone, Copy)]
+pub enum LuminousPower {
+    Watts(f64),
+    Lumens(f64)
+}
+
+// lumens per radiant watt emitted by a blackbody, whose total radiance is sigma T^4 / pi
+pub fn luminous_efficacy(temperature: f64) -> f64 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+
+    let (_, y, _) = blackbody_xyz(temperature);
+    MAX_LUMINOUS_EFFICACY * y / (STEFAN_BOLTZMANN * temperature.powi(4) / std::f64::consts::PI)
+}
+
+// luminance (cd/m^2, taking scene units as metres) of a one sided lambertian emitter of some area
+// giving out the power
+pub fn luminance_from_power(temperature: f64, power: LuminousPower, area: f64) -> f64 {
+    let lumens = match power {
+        LuminousPower::Lumens(lumens) => lumens,
+        LuminousPower::Watts(watts) => watts * luminous_efficacy(temperature)
+    };
+
+    lumens / (std::f64::consts::PI * area)
+}
+
+// Colour temperatures given by a texture only have to be integrated where they are looked up,
+// a single temperature is integrated once up front
+enum Temperature {
+    Constant(Color),
+    Varying(Box<dyn Texture>, f64)
+}
+
+pub struct BlackbodyTexture {
+    temperature: Temperature
+}
+
+impl BlackbodyTexture {
+    // the red channel of the texture is the temperature in kelvin
+    pub fn new(temperature: Box<dyn Texture>, intensity: f64) -> BlackbodyTexture {
+        BlackbodyTexture {
+            temperature: Temperature::Varying(temperature, intensity)
+        }
+    }
+
+    // a single colour temperature spread over an emitter's area to give out the power
+    pub fn from_power(temperature: f64, power: LuminousPower, area: f64) -> BlackbodyTexture {
+        BlackbodyTexture {
+            temperature: Temperature::Constant(luminance_from_power(temperature, power, area) * blackbody(temperature))
+        }
+    }
+}
+
+impl Texture for BlackbodyTexture {
+    fn get_color(&self, u: f64, v: f64, point: &Point3) -> Color {
+        match &self.temperature {
+            Temperature::Constant(color) => *color,
+            Temperature::Varying(temperature, intensity) => *intensity * blackbody(temperature.get_color(u, v, point).x())
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::{io, sync::Arc};
 
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::{color::{self, Color}, common::{self, degrees_to_radians, random_double, random_double_range}, hittable::Hittable, hittable_list::HittableList, pdf::{CosinePdf, HittablePdf, MixturePdf, Pdf}, ray::Ray, vec2::UV, vec3::{self, dot, Point3, Vec3}};
+use crate::{background::Background, color::{self, Color}, common::{self, degrees_to_radians, random_double, random_double_range}, constant_medium::volume_hit_record, fog::Fog, hittable::{HitRecord, Hittable}, hittable_list::HittableList, light::LightList, material::{Interior, Material}, pdf::{CosinePdf, LightPdf}, ray::Ray, vec2::UV, vec3::{self, dot, Point3, Vec3}};
 
 pub struct Camera {
     image_width: i32,
@@ -16,8 +16,9 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     lens_radius: f64,
-    background: Color,
+    background: Arc<dyn Background>,
     sqrt_samples: i32,
+    fog: Option<Fog>,
 }
 
 impl Camera {
@@ -54,12 +55,21 @@ impl Camera {
             u,
             v,
             lens_radius,
-            background,
-            sqrt_samples
+            background: Arc::new(background),
+            sqrt_samples,
+            fog: None
         }
     }
 
-    pub fn render(&self, world: &HittableList, lights: Arc<dyn Hittable>) {
+    pub fn set_fog(&mut self, fog: Fog) {
+        self.fog = Some(fog);
+    }
+
+    pub fn set_background(&mut self, background: Arc<dyn Background>) {
+        self.background = background;
+    }
+
+    pub fn render(&self, world: &HittableList, lights: Arc<LightList>) {
         print!("P3\n{} {}\n255\n", self.image_width, self.image_height);
         for j in (0..self.image_height).rev() {
             eprint!("\rScanlines remaining: {}", j);
@@ -72,7 +82,7 @@ impl Camera {
                             let u = (i as f64 + random_double()) / (self.image_width - 1) as f64;
                             let v = (j as f64 + random_double()) / (self.image_height - 1) as f64;
                             let r = self.get_ray(u, v, s_i, s_j);
-                            pixel_color += self.ray_color(&r, world, lights.clone(), self.max_depth);
+                            pixel_color += self.ray_color(&r, world, lights.clone(), self.max_depth, &[]);
                         }
                     }
                     pixel_color
@@ -86,37 +96,168 @@ impl Camera {
         eprint!("\nDone.\n");
     }
 
-    fn ray_color(&self, ray: &Ray, world: &dyn Hittable, lights: Arc<dyn Hittable>, depth: i32) -> Color {
+    // interior lists the nestable media the ray is currently inside, in the order they were entered
+    fn ray_color(&self, ray: &Ray, world: &dyn Hittable, lights: Arc<LightList>, depth: i32, interior: &[Interior]) -> Color {
         if depth <= 0 {
             return Color::new(0.0, 0.0, 0.0);
         }
-    
-        if let Some(hit_rec) = world.hit(ray, 0.001, common::INFINITY) {
-            let color_from_emission = hit_rec.mat.emitted(&hit_rec, hit_rec.u, hit_rec.v, &hit_rec.p);
-
-            return match hit_rec.mat.scatter(ray, &hit_rec) {
-                Some(scatter_rec) => {
-                    if let None = scatter_rec.pdf {
-                        return scatter_rec.attenuation * self.ray_color(&scatter_rec.scattered, world, lights.clone(), depth - 1);
-                    }
-                    let light_pdf = HittablePdf::new(hit_rec.p, lights.clone());
-                    let mixture_pdf = MixturePdf::new(Arc::new(light_pdf), scatter_rec.pdf.unwrap().clone());
-                    
-                    let scattered_ray = Ray::new(hit_rec.p, mixture_pdf.generate(), ray.time());
-                    let pdf_value = mixture_pdf.value(scattered_ray.direction());
 
-                    let scattered_pdf = hit_rec.mat.scatter_pdf(ray, &hit_rec, &scattered_ray);
+        let hit_rec = world.hit(ray, 0.001, common::INFINITY);
+        let t_max = hit_rec.as_ref().map_or(common::INFINITY, |hit_rec| hit_rec.t);
 
-                    let sample_color = self.ray_color(&scattered_ray, world, lights.clone(), depth - 1);
-                    let color_from_scatter = (scatter_rec.attenuation * scattered_pdf * sample_color) / pdf_value;
+        // inside a dielectric the ray is absorbed by it rather than travelling through the fog
+        if let Some(medium) = Self::innermost(interior) {
+            let distance = t_max * ray.direction().length();
+            let transmittance = |absorption: f64| if absorption > 0.0 {f64::exp(-absorption * distance)} else {1.0};
+            let attenuation = Color::new(transmittance(medium.absorption.x()), transmittance(medium.absorption.y()), transmittance(medium.absorption.z()));
+            if attenuation.near_zero() {
+                return Color::new(0.0, 0.0, 0.0);
+            }
 
-                    return color_from_emission + color_from_scatter;
-                },
-                None => color_from_emission
+            return match hit_rec {
+                Some(hit_rec) => attenuation * self.shade(ray, &hit_rec, world, lights, depth, interior),
+                None => attenuation * self.background.color(ray.direction())
             };
-        } else {
-            return self.background;
         }
+
+        // the fog may scatter the ray before it reaches whatever it hit, or before it escapes
+        if let Some(fog) = &self.fog {
+            if let Some(t) = fog.sample(ray, t_max) {
+                let fog_rec = volume_hit_record(ray, t, fog.phase_function());
+                return self.shade(ray, &fog_rec, world, lights, depth, interior);
+            }
+        }
+
+        match hit_rec {
+            Some(hit_rec) => self.shade(ray, &hit_rec, world, lights, depth, interior),
+            None => self.background.color(ray.direction())
+        }
+    }
+
+    fn shade(&self, ray: &Ray, hit_rec: &HitRecord, world: &dyn Hittable, lights: Arc<LightList>, depth: i32, interior: &[Interior]) -> Color {
+        if let Some(medium) = hit_rec.mat.interior() {
+            return self.shade_interface(ray, hit_rec, medium, world, lights, depth, interior);
+        }
+
+        let color_from_emission = hit_rec.mat.emitted(ray, hit_rec, hit_rec.u, hit_rec.v, &hit_rec.p);
+
+        match hit_rec.mat.scatter(ray, hit_rec) {
+            Some(scatter_rec) => {
+                if let None = scatter_rec.pdf {
+                    return scatter_rec.attenuation * self.ray_color(&scatter_rec.scattered, world, lights.clone(), depth - 1, interior);
+                }
+                let material_pdf = scatter_rec.pdf.clone().unwrap();
+                // a mixed material answers for the lobe it picked
+                let material = scatter_rec.lobe.clone().unwrap_or_else(|| hit_rec.mat.clone());
+                let light_pdf = LightPdf::new(hit_rec.p, ray.time(), lights.clone());
+                // with no area light able to reach here, sampling them would only waste half the rays
+                let mixed = lights.illuminates(hit_rec.p);
+                let direction = if mixed && random_double() < 0.5 {light_pdf.generate()} else {Some(material_pdf.generate())};
+
+                // a light that can't give a direction wastes the sample, keeping the same mixture
+                let color_from_scatter = match direction {
+                    Some(direction) => {
+                        let pdf_value = if mixed {
+                            0.5 * light_pdf.value(direction) + 0.5 * material_pdf.value(direction)
+                        } else {
+                            material_pdf.value(direction)
+                        };
+
+                        let scattered_ray = Ray::new(hit_rec.p, direction, ray.time());
+                        let scattered_pdf = material.scatter_pdf(ray, hit_rec, &scattered_ray);
+
+                        let sample_color = self.ray_color(&scattered_ray, world, lights.clone(), depth - 1, interior);
+                        (scatter_rec.attenuation * scattered_pdf * sample_color) / pdf_value
+                    },
+                    None => Color::new(0.0, 0.0, 0.0)
+                };
+
+                let color_from_delta_lights = self.sample_delta_lights(ray, hit_rec, material.as_ref(), scatter_rec.attenuation, world, &lights);
+
+                color_from_emission + color_from_scatter + color_from_delta_lights
+            },
+            None => color_from_emission
+        }
+    }
+
+    // delta lights can't be found by scattering, so each one is connected to with a shadow ray
+    fn sample_delta_lights(&self, ray: &Ray, hit_rec: &HitRecord, material: &dyn Material, attenuation: Color, world: &dyn Hittable, lights: &LightList) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        for light in lights.delta_lights() {
+            let light_sample = match light.sample_li(hit_rec.p, ray.time()) {
+                Some(light_sample) => light_sample,
+                None => continue
+            };
+
+            let shadow_ray = Ray::new(hit_rec.p, light_sample.direction, ray.time());
+            let scattered_pdf = material.scatter_pdf(ray, hit_rec, &shadow_ray);
+            if scattered_pdf <= 0.0 || light_sample.pdf <= 0.0 {
+                continue;
+            }
+
+            // media along the way dim the light rather than block it outright
+            let transmittance = world.transmittance(&shadow_ray, 0.001, light_sample.distance);
+            if transmittance <= 0.0 {
+                continue;
+            }
+            // fog that would scatter the shadow ray first blocks it
+            if let Some(fog) = &self.fog {
+                if fog.sample(&shadow_ray, light_sample.distance).is_some() {
+                    continue;
+                }
+            }
+
+            color += transmittance * attenuation * scattered_pdf * light_sample.radiance / light_sample.pdf;
+        }
+
+        color
+    }
+
+    // Surfaces of nestable media follow Schmidt and Budge's priority rules: a surface inside a
+    // higher priority medium is ignored, and otherwise refracts between this medium and the one
+    // that surrounds it rather than assuming the outside is air.
+    #[allow(clippy::too_many_arguments)]
+    fn shade_interface(&self, ray: &Ray, hit_rec: &HitRecord, medium: Interior, world: &dyn Hittable, lights: Arc<LightList>, depth: i32, interior: &[Interior]) -> Color {
+        let entering = hit_rec.front_face;
+
+        let mut outside = interior.to_vec();
+        if let Some(index) = outside.iter().rposition(|m| m.id == medium.id) {
+            outside.remove(index);
+        }
+        let mut inside = outside.clone();
+        inside.push(medium);
+
+        let surrounding = Self::innermost(&outside);
+        if let Some(surrounding) = surrounding {
+            if surrounding.priority > medium.priority {
+                let continued = Ray::new(hit_rec.p, ray.direction(), ray.time());
+                let interior_after = if entering {&inside} else {&outside};
+                return self.ray_color(&continued, world, lights, depth - 1, interior_after);
+            }
+        }
+
+        let surrounding_ior = surrounding.map_or(1.0, |m| m.ior);
+        let (incident_ior, transmitted_ior) = if entering {(surrounding_ior, medium.ior)} else {(medium.ior, surrounding_ior)};
+
+        let color_from_emission = hit_rec.mat.emitted(ray, hit_rec, hit_rec.u, hit_rec.v, &hit_rec.p);
+        let scatter_rec = match hit_rec.mat.scatter_between(ray, hit_rec, incident_ior, transmitted_ior) {
+            Some(scatter_rec) => scatter_rec,
+            None => return color_from_emission
+        };
+
+        let transmitted = dot(scatter_rec.scattered.direction(), hit_rec.normal) < 0.0;
+        let interior_after = match (transmitted, entering) {
+            (false, _) => interior,
+            (true, true) => &inside,
+            (true, false) => &outside
+        };
+
+        color_from_emission + scatter_rec.attenuation * self.ray_color(&scatter_rec.scattered, world, lights, depth - 1, interior_after)
+    }
+
+    // the medium that wins where several overlap, the most recently entered on ties
+    fn innermost(interior: &[Interior]) -> Option<Interior> {
+        interior.iter().max_by_key(|m| m.priority).copied()
     }
 
     fn get_ray(&self, s: f64, t: f64, s_i: i32, s_j: i32) -> Ray {
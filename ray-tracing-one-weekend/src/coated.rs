@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::{color::Color, common::random_double, hittable::HitRecord, light::luminance, material::{Dielectric, EmissionSpread, Material, ScatterRecord}, ray::Ray, vec3::{self, Point3}};
+
+pub struct Coated {
+    base: Arc<dyn Material>,
+    ior: f64,
+    thickness: f64,
+    roughness: f64,
+    absorption: Color
+}
+
+impl Coated {
+    pub fn new(base: Arc<dyn Material>, ior: f64, thickness: f64, roughness: f64, absorption: Color) -> Coated {
+        Coated {
+            base,
+            ior,
+            thickness,
+            roughness: if roughness < 1.0 {roughness} else {1.0},
+            absorption
+        }
+    }
+
+    pub fn clear(base: Arc<dyn Material>, ior: f64, roughness: f64) -> Coated {
+        Coated::new(base, ior, 0.0, roughness, Color::new(0.0, 0.0, 0.0))
+    }
+
+    // cosine of a direction with the normal once refracted into the coating
+    fn refracted_cosine(&self, cosine: f64) -> f64 {
+        let sin_sq = (1.0 - cosine * cosine) / (self.ior * self.ior);
+        f64::sqrt(1.0 - sin_sq)
+    }
+
+    fn coating_transmittance(&self, cosine: f64) -> Color {
+        let distance = self.thickness / self.refracted_cosine(cosine);
+
+        Color::new(
+            f64::exp(-self.absorption.x() * distance),
+            f64::exp(-self.absorption.y() * distance),
+            f64::exp(-self.absorption.z() * distance),
+        )
+    }
+}
+
+impl Material for Coated {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let unit_direction = vec3::unit_vector(r_in.direction());
+        let cos_in = f64::clamp(vec3::dot(-unit_direction, rec.normal), 0.0, 1.0);
+
+        // the coating reflects with probability given by its fresnel term, otherwise the base layer scatters
+        if Dielectric::reflectance(cos_in, self.ior) > random_double() {
+            let reflected = vec3::reflect(unit_direction, rec.normal);
+            let scattered = Ray::new(rec.p, reflected + self.roughness * vec3::random_in_unit_sphere(), r_in.time());
+            if vec3::dot(scattered.direction(), rec.normal) <= 0.0 {
+                return None;
+            }
+
+            return Some(ScatterRecord {
+                attenuation: Color::new(1.0, 1.0, 1.0),
+                scattered,
+                pdf: None,
+                lobe: None
+            });
+        }
+
+        let mut scatter_rec = self.base.scatter(r_in, rec)?;
+        scatter_rec.attenuation *= self.coating_transmittance(cos_in);
+
+        if scatter_rec.pdf.is_none() {
+            let cos_out = f64::clamp(vec3::dot(vec3::unit_vector(scatter_rec.scattered.direction()), rec.normal), 0.0, 1.0);
+            let exit_probability = 1.0 - Dielectric::reflectance(cos_out, self.ior);
+            scatter_rec.attenuation *= exit_probability * self.coating_transmittance(cos_out);
+        } else {
+            // the exit direction is only known in scatter_pdf; the colour of leaving straight out
+            // goes here and scatter_pdf scales it for the longer slanted paths
+            scatter_rec.attenuation *= self.coating_transmittance(1.0);
+        }
+
+        // a lobe picked underneath still has to go out through the coating
+        scatter_rec.lobe = scatter_rec.lobe.map(|lobe| Arc::new(Coated {
+            base: lobe,
+            ior: self.ior,
+            thickness: self.thickness,
+            roughness: self.roughness,
+            absorption: self.absorption
+        }) as Arc<dyn Material>);
+
+        Some(scatter_rec)
+    }
+
+    fn emitted(&self, r_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        self.base.emitted(r_in, hit_rec, u, v, p)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.base.is_emissive()
+    }
+
+    fn emission_spread(&self) -> EmissionSpread {
+        self.base.emission_spread()
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_out = f64::clamp(vec3::dot(vec3::unit_vector(scattered.direction()), rec.normal), 0.0, 1.0);
+        let exit_probability = 1.0 - Dielectric::reflectance(cos_out, self.ior);
+
+        // scatter_pdf is a single number, so the extra absorption of slanted exits is taken by
+        // luminance, exact for grey coatings
+        let straight_out = luminance(self.coating_transmittance(1.0));
+        let exit_transmittance = if straight_out > 0.0 {luminance(self.coating_transmittance(cos_out)) / straight_out} else {0.0};
+
+        exit_probability * exit_transmittance * self.base.scatter_pdf(r_in, rec, scattered)
+    }
+}
@@ -1,7 +1,7 @@
 use core::f64;
 use std::sync::Arc;
 
-use crate::{color::Color, common::random_double, hittable::{HitRecord, Hittable}, material::{Isotropic, Material}, vec3::Vec3};
+use crate::{color::Color, common::random_double, hittable::{HitRecord, Hittable}, light::LightList, material::{EmissiveIsotropic, Isotropic, Material}, ray::Ray, texture::Texture, vec3::Vec3};
 
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable>,
@@ -28,55 +28,193 @@ impl ConstantMedium {
             phase_function
         }
     }
+
+    pub fn emissive(boundary: Arc<dyn Hittable>, density: f64, albedo: Color, emission: Box<dyn Texture>) -> Self {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function: Arc::new(EmissiveIsotropic::from_color(albedo, emission))
+        }
+    }
 }
 
 impl Hittable for ConstantMedium {
-    fn hit(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> Option<crate::hittable::HitRecord> {
-        let hit_rec_one =  self.boundary.hit(ray, -f64::INFINITY, f64::INFINITY);
-        if hit_rec_one.is_none() {
-            return None;
-        }
-        let mut hit_rec_one = hit_rec_one.unwrap();
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<crate::hittable::HitRecord> {
+        let segments = boundary_segments(self.boundary.as_ref(), ray, t_min, t_max);
 
-        let hit_rec_two = self.boundary.hit(ray, hit_rec_one.t + 0.0001, f64::INFINITY);
-        if hit_rec_two.is_none() {
-            return None;
-        }
-        let mut hit_rec_two = hit_rec_two.unwrap();
+        let ray_length = ray.direction().length();
+        let mut hit_distance = self.neg_inv_density * f64::ln(random_double());
 
-        if hit_rec_one.t < t_min {
-            hit_rec_one.t = t_min;
-        }
-        if hit_rec_two.t > t_max {
-            hit_rec_two.t = t_max;
+        // spend the sampled distance across the stretches of the ray that are inside the boundary
+        for (t_enter, t_exit) in segments {
+            let distance_inside_segment = (t_exit - t_enter) * ray_length;
+            if hit_distance <= distance_inside_segment {
+                let t = t_enter + hit_distance / ray_length;
+                return Some(volume_hit_record(ray, t, self.phase_function.clone()));
+            }
+            hit_distance -= distance_inside_segment;
         }
 
-        if hit_rec_one.t >= hit_rec_two.t {
-            return None;
+        None
+    }
+
+    // a glowing volume has no surface to sample, so scattered rays are left to find it
+    fn collect_lights(self: Arc<Self>, _lights: &mut LightList) {}
+}
+
+// The stretches of [t_min, t_max] the ray spends inside the boundary, in order.
+//
+// Crossings are walked along the whole line, counting entries through front faces and exits
+// through back faces, so concave, nested and overlapping boundaries work as does starting inside.
+// An exit with nothing entered means the boundary is open behind it, so the ray was inside until then.
+pub fn boundary_segments(boundary: &dyn Hittable, ray: &Ray, t_min: f64, t_max: f64) -> Vec<(f64, f64)> {
+    let mut segments = Vec::new();
+    let mut depth = 0;
+    let mut entered = -f64::INFINITY;
+    let mut last_crossing = -f64::INFINITY;
+    let mut t = -f64::INFINITY;
+
+    while let Some(hit_rec) = boundary.hit(ray, t, f64::INFINITY) {
+        if hit_rec.front_face {
+            if depth == 0 {
+                entered = hit_rec.t;
+            }
+            depth += 1;
+        } else if depth > 0 {
+            depth -= 1;
+            if depth == 0 {
+                segments.push((entered, hit_rec.t));
+            }
+        } else {
+            segments.push((last_crossing, hit_rec.t));
         }
-        
-        if hit_rec_one.t < 0.0 {
-            hit_rec_one.t = 0.0;
+
+        last_crossing = hit_rec.t;
+        t = hit_rec.t + 0.0001;
+    }
+
+    if depth > 0 {
+        segments.push((entered, f64::INFINITY));
+    }
+
+    segments.into_iter()
+        .map(|(t_enter, t_exit)| (f64::max(f64::max(t_enter, t_min), 0.0), f64::min(t_exit, t_max)))
+        .filter(|(t_enter, t_exit)| t_enter < t_exit)
+        .collect()
+}
+
+// a scattering event inside a volume has no surface, so the normal and uv are arbitrary
+pub fn volume_hit_record(ray: &Ray, t: f64, mat: Arc<dyn Material>) -> HitRecord {
+    HitRecord {
+        p: ray.at(t),
+        normal: Vec3::new(1.0, 0.0, 0.0), //arbitrary
+        mat,
+        t,
+        u: 0.0,
+        v: 0.0,
+        front_face: true, //arbitrary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{common::degrees_to_radians, hittable_list::HittableList, material::Empty, quad::Quad, sphere::Sphere, transfomation::{RotateY, Translate}, vec3::Point3};
+
+    fn sphere(center: Point3, radius: f64) -> Arc<Sphere> {
+        Arc::new(Sphere::new(Ray::new(center, Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Empty::new()), radius))
+    }
+
+    fn assert_segments(segments: &[(f64, f64)], expected: &[(f64, f64)]) {
+        assert_eq!(segments.len(), expected.len(), "{:?}", segments);
+        for ((t_enter, t_exit), (expected_enter, expected_exit)) in segments.iter().zip(expected) {
+            assert!(f64::abs(t_enter - expected_enter) < 1e-6, "{:?}", segments);
+            assert!(f64::abs(t_exit - expected_exit) < 1e-6, "{:?}", segments);
         }
+    }
 
-        let ray_length = ray.direction().length();
-        let distance_inside_boundary = (hit_rec_two.t - hit_rec_one.t) * ray_length;
-        let hit_distance = self.neg_inv_density * f64::ln(random_double());
+    #[test]
+    fn concave_and_nested_boundaries() {
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
 
-        if hit_distance > distance_inside_boundary {
-            return None;
+        // two separate boxes, the ray leaves the medium in between
+        let mut pair = HittableList::new();
+        pair.add(Arc::new(Quad::get_box(Point3::new(-3.0, -1.0, -1.0), Point3::new(-1.0, 1.0, 1.0), Arc::new(Empty::new()))));
+        pair.add(Arc::new(Quad::get_box(Point3::new(1.0, -1.0, -1.0), Point3::new(3.0, 1.0, 1.0), Arc::new(Empty::new()))));
+        assert_segments(&boundary_segments(&pair, &ray, 0.001, f64::INFINITY), &[(2.0, 4.0), (6.0, 8.0)]);
+
+        // a sphere inside another only counts once
+        let mut nested = HittableList::new();
+        nested.add(sphere(Point3::new(0.0, 0.0, 0.0), 2.0));
+        nested.add(sphere(Point3::new(0.0, 0.0, 0.0), 1.0));
+        assert_segments(&boundary_segments(&nested, &ray, 0.001, f64::INFINITY), &[(3.0, 7.0)]);
+    }
+
+    // the torus-like ring of overlapping boxes from the concave fog scene, with each box's
+    // centre and the angle it is turned by
+    fn ring() -> (HittableList, Vec<(Point3, f64)>) {
+        let mut ring = HittableList::new();
+        let mut placements = Vec::new();
+        for i in 0..12 {
+            let angle = i as f64 * 30.0;
+            let radians = degrees_to_radians(angle);
+            let center = Point3::new(3.0 * f64::cos(radians), 1.5, 3.0 * f64::sin(radians));
+            let segment = Arc::new(Quad::get_box(Point3::new(-0.9, -0.9, -0.9), Point3::new(0.9, 0.9, 0.9), Arc::new(Empty::new())));
+            ring.add(Arc::new(Translate::new(Arc::new(RotateY::new(segment, -angle)), center)));
+            placements.push((center, -angle));
         }
 
-        let t = hit_rec_one.t + hit_distance / ray_length;
+        (ring, placements)
+    }
 
-        Some(HitRecord {
-            p: ray.at(t),
-            normal: Vec3::new(1.0, 0.0, 0.0), //arbitrary
-            mat: self.phase_function.clone(),
-            t: t,
-            u: 0.0,
-            v: 0.0,
-            front_face: true, //arbitrary
+    fn inside_ring(placements: &[(Point3, f64)], p: Point3) -> bool {
+        placements.iter().any(|&(center, angle)| {
+            let (sin_theta, cos_theta) = (f64::sin(degrees_to_radians(angle)), f64::cos(degrees_to_radians(angle)));
+            let d = p - center;
+            let local = Vec3::new(cos_theta * d.x() - sin_theta * d.z(), d.y(), sin_theta * d.x() + cos_theta * d.z());
+            f64::abs(local.x()) <= 0.9 && f64::abs(local.y()) <= 0.9 && f64::abs(local.z()) <= 0.9
         })
     }
-}
\ No newline at end of file
+
+    fn total_length(segments: &[(f64, f64)]) -> f64 {
+        segments.iter().map(|(t_enter, t_exit)| t_exit - t_enter).sum()
+    }
+
+    #[test]
+    fn concave_ring() {
+        let (ring, placements) = ring();
+
+        // across the middle the ray leaves the ring through the hole and comes back in
+        let across = Ray::new(Point3::new(-10.0, 1.5, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let segments = boundary_segments(&ring, &across, 0.001, f64::INFINITY);
+        assert_segments(&segments, &[(6.1, 7.9), (12.1, 13.9)]);
+        assert!(f64::abs(total_length(&segments) - 3.6) < 1e-6);
+
+        // along the ring the boxes overlap, so check against stepping through the inside
+        let along = Ray::new(Point3::new(2.8, 1.5, -10.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let segments = boundary_segments(&ring, &along, 0.001, f64::INFINITY);
+        let step = 1e-4;
+        let expected = (0..200000).filter(|&i| inside_ring(&placements, along.at((i as f64 + 0.5) * step))).count() as f64 * step;
+        assert!(f64::abs(total_length(&segments) - expected) < 1e-3, "{:?} against {}", segments, expected);
+    }
+
+    #[test]
+    fn ray_starting_inside_begins_at_t_min() {
+        let boundary = sphere(Point3::new(0.0, 0.0, 0.0), 1.0);
+        let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+
+        assert_segments(&boundary_segments(boundary.as_ref(), &ray, 0.001, f64::INFINITY), &[(0.001, 1.0)]);
+    }
+
+    #[test]
+    fn open_boundary() {
+        // a single wall facing +x, so everything behind it counts as inside
+        let wall = Quad::new(Point3::new(1.0, -1.0, -1.0), Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 2.0), Arc::new(Empty::new()));
+
+        let leaving = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        assert_segments(&boundary_segments(&wall, &leaving, 0.001, 10.0), &[(0.001, 1.0)]);
+
+        let entering = Ray::new(Point3::new(2.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        assert_segments(&boundary_segments(&wall, &entering, 0.001, 10.0), &[(1.0, 10.0)]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use crate::{color::Color, common::{degrees_to_radians, PI}, hittable::HitRecord, material::{Material, ScatterRecord}, onb::Onb, pdf::CosinePdf, ray::Ray, texture::{SolidColor, Texture}, vec3::{self, unit_vector, Vec3}};
+
+// cosine weighted scatter shared by the diffuse models, which then only differ in scatter_pdf
+fn scatter_cosine(albedo: &dyn Texture, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+    let uvw = Onb::new(&rec.normal);
+    let scatter_direction = uvw.transform(Vec3::random_cosine_direction());
+
+    Some(ScatterRecord {
+        attenuation: albedo.get_color(rec.u, rec.v, &rec.p),
+        scattered: Ray::new(rec.p, scatter_direction, r_in.time()),
+        pdf: Some(Arc::new(CosinePdf::new(rec.normal))),
+        lobe: None
+    })
+}
+
+pub struct OrenNayar {
+    albedo: Box<dyn Texture>,
+    a: f64,
+    b: f64
+}
+
+impl OrenNayar {
+    // roughness is the standard deviation of the microfacet angle in degrees
+    pub fn new(albedo: Box<dyn Texture>, roughness: f64) -> OrenNayar {
+        let sigma = degrees_to_radians(roughness);
+        let sigma_sq = sigma * sigma;
+
+        OrenNayar {
+            albedo,
+            a: 1.0 - 0.5 * sigma_sq / (sigma_sq + 0.33),
+            b: 0.45 * sigma_sq / (sigma_sq + 0.09)
+        }
+    }
+
+    pub fn from_color(albedo_color: Color, roughness: f64) -> OrenNayar {
+        OrenNayar::new(Box::new(SolidColor::new(albedo_color)), roughness)
+    }
+}
+
+impl Material for OrenNayar {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        scatter_cosine(self.albedo.as_ref(), r_in, rec)
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let wi = -unit_vector(r_in.direction());
+        let wo = unit_vector(scattered.direction());
+        let cos_i = f64::clamp(vec3::dot(rec.normal, wi), 0.0, 1.0);
+        let cos_o = vec3::dot(rec.normal, wo);
+        if cos_o <= 0.0 {
+            return 0.0;
+        }
+
+        let sin_i = f64::sqrt(1.0 - cos_i * cos_i);
+        let sin_o = f64::sqrt(1.0 - cos_o * cos_o);
+
+        // cosine of the azimuth between the two directions in the tangent plane
+        let cos_phi = if sin_i > 1e-4 && sin_o > 1e-4 {
+            let tangent_i = unit_vector(wi - cos_i * rec.normal);
+            let tangent_o = unit_vector(wo - cos_o * rec.normal);
+            f64::max(0.0, vec3::dot(tangent_i, tangent_o))
+        } else {
+            0.0
+        };
+
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else {
+            (sin_i, sin_o / cos_o)
+        };
+
+        (self.a + self.b * cos_phi * sin_alpha * tan_beta) * cos_o / PI
+    }
+}
+
+pub struct LommelSeeliger {
+    albedo: Box<dyn Texture>,
+    opposition_strength: f64,
+    opposition_width: f64
+}
+
+impl LommelSeeliger {
+    pub fn new(albedo: Box<dyn Texture>) -> LommelSeeliger {
+        LommelSeeliger {
+            albedo,
+            opposition_strength: 0.0,
+            opposition_width: 1.0
+        }
+    }
+
+    pub fn from_color(albedo_color: Color) -> LommelSeeliger {
+        LommelSeeliger::new(Box::new(SolidColor::new(albedo_color)))
+    }
+
+    // Hapke's opposition surge brightens the surface when lit from behind the viewer
+    pub fn hapke(albedo: Box<dyn Texture>, opposition_strength: f64, opposition_width: f64) -> LommelSeeliger {
+        LommelSeeliger {
+            albedo,
+            opposition_strength,
+            opposition_width
+        }
+    }
+}
+
+impl Material for LommelSeeliger {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        scatter_cosine(self.albedo.as_ref(), r_in, rec)
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let wi = -unit_vector(r_in.direction());
+        let wo = unit_vector(scattered.direction());
+        let cos_i = f64::max(0.0, vec3::dot(rec.normal, wi));
+        let cos_o = vec3::dot(rec.normal, wo);
+        if cos_o <= 0.0 {
+            return 0.0;
+        }
+
+        let phase_angle = f64::acos(f64::clamp(vec3::dot(wi, wo), -1.0, 1.0));
+        let opposition = self.opposition_strength / (1.0 + f64::tan(phase_angle / 2.0) / self.opposition_width);
+
+        (1.0 + opposition) * cos_o / (4.0 * PI * (cos_i + cos_o))
+    }
+}
+
+pub struct Sheen {
+    albedo: Box<dyn Texture>,
+    roughness: f64
+}
+
+impl Sheen {
+    pub fn new(albedo: Box<dyn Texture>, roughness: f64) -> Sheen {
+        Sheen {
+            albedo,
+            roughness: f64::clamp(roughness, 0.01, 1.0)
+        }
+    }
+
+    pub fn from_color(albedo_color: Color, roughness: f64) -> Sheen {
+        Sheen::new(Box::new(SolidColor::new(albedo_color)), roughness)
+    }
+}
+
+impl Material for Sheen {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        scatter_cosine(self.albedo.as_ref(), r_in, rec)
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let wi = -unit_vector(r_in.direction());
+        let wo = unit_vector(scattered.direction());
+        let cos_i = vec3::dot(rec.normal, wi);
+        let cos_o = vec3::dot(rec.normal, wo);
+        if cos_i <= 0.0 || cos_o <= 0.0 {
+            return 0.0;
+        }
+
+        // Estevez and Kulla's "Charlie" distribution with Neubelt and Pettineo's visibility term
+        let alpha = self.roughness * self.roughness;
+        let cos_h = vec3::dot(rec.normal, unit_vector(wi + wo));
+        let sin_h = f64::sqrt(f64::max(0.0, 1.0 - cos_h * cos_h));
+        let distribution = (2.0 + 1.0 / alpha) * f64::powf(sin_h, 1.0 / alpha) / (2.0 * PI);
+        let visibility = 1.0 / (4.0 * (cos_i + cos_o - cos_i * cos_o));
+
+        distribution * visibility * cos_o
+    }
+}
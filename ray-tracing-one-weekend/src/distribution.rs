@@ -0,0 +1,102 @@
+// Piecewise constant distributions for importance sampling tabulated functions.
+
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Distribution1D {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + f64::abs(func[i]) / n as f64;
+        }
+
+        let integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            // an all zero function falls back to uniform sampling
+            *c = if integral > 0.0 {*c / integral} else {i as f64 / n as f64};
+        }
+
+        Distribution1D {
+            func,
+            cdf,
+            integral
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    // maps u in [0, 1) to a point in [0, 1) returning it, its density and the bucket it fell in
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let offset = usize::min(self.cdf.partition_point(|&c| c <= u).saturating_sub(1), self.count() - 1);
+
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 {(u - self.cdf[offset]) / width} else {0.0};
+
+        (((offset as f64 + du) / self.count() as f64), self.pdf(offset), offset)
+    }
+
+    // probability of sample landing in the bucket at offset
+    pub fn pmf(&self, offset: usize) -> f64 {
+        self.pdf(offset) / self.count() as f64
+    }
+
+    // density of the bucket at offset with respect to [0, 1)
+    pub fn pdf(&self, offset: usize) -> f64 {
+        if self.integral > 0.0 {
+            f64::abs(self.func[offset]) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+// A function over [0, 1)^2 given as rows of values, sampled by picking a row from the
+// marginal distribution and then a column within it.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f64], width: usize, height: usize) -> Distribution2D {
+        assert_eq!(func.len(), width * height, "distribution should have a value per cell");
+
+        let conditional: Vec<Distribution1D> = func.chunks(width).map(|row| Distribution1D::new(row.to_vec())).collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|row| row.integral()).collect());
+
+        Distribution2D {
+            conditional,
+            marginal
+        }
+    }
+
+    // returns (u, v) and the density with respect to area in [0, 1)^2
+    pub fn sample(&self, u0: f64, u1: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(u1);
+        let (u, pdf_u, _) = self.conditional[row].sample(u0);
+
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let row = usize::min((v * self.marginal.count() as f64) as usize, self.marginal.count() - 1);
+        let conditional = &self.conditional[row];
+        let column = usize::min((u * conditional.count() as f64) as usize, conditional.count() - 1);
+
+        if self.marginal.integral() > 0.0 {
+            f64::abs(conditional.func[column]) / self.marginal.integral()
+        } else {
+            1.0
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{background::Background, color::Color, common::{degrees_to_radians, random_double}, distribution::Distribution2D, light::{luminance, Light, LightSample}, light_bvh::LightBounds, vec3::{unit_vector, Point3, Vec3}};
+
+// An equirectangular image of the surroundings, centred on -z with +y up.
+//
+// Anything the image crate can decode works, but only high dynamic range .hdr or .exr
+// files carry enough range to light a scene convincingly.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    intensity: f64,
+    // rotation about the y axis, in radians
+    rotation: f64,
+    distribution: Distribution2D,
+    // average over solid angle
+    mean_luminance: f64
+}
+
+impl EnvironmentMap {
+    pub fn new(image_file: &str, intensity: f64, rotation_degrees: f64) -> Result<EnvironmentMap, String> {
+        let image = image::open(image_file).map_err(|e| format!("couldn't read environment map {}: {}", image_file, e))?.to_rgb32f();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        if width == 0 || height == 0 {
+            return Err(format!("environment map {} is empty", image_file));
+        }
+
+        let pixels = image.pixels().map(|p| Color::new(p.0[0] as f64, p.0[1] as f64, p.0[2] as f64)).collect();
+
+        Ok(EnvironmentMap::from_pixels(width, height, pixels, intensity, rotation_degrees))
+    }
+
+    // pixels are row by row from the top of the image
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>, intensity: f64, rotation_degrees: f64) -> EnvironmentMap {
+        assert_eq!(pixels.len(), width * height, "environment map should have every pixel");
+
+        // weight by sin(theta) so rows squeezed together near the poles are picked less
+        let sin_theta = |i: usize| f64::sin(std::f64::consts::PI * ((i / width) as f64 + 0.5) / height as f64);
+        let weighted: Vec<f64> = pixels.iter().enumerate().map(|(i, c)| luminance(*c) * sin_theta(i)).collect();
+        let mean_luminance = weighted.iter().sum::<f64>() / (0..pixels.len()).map(sin_theta).sum::<f64>();
+
+        EnvironmentMap {
+            width,
+            height,
+            pixels,
+            intensity,
+            rotation: degrees_to_radians(rotation_degrees),
+            distribution: Distribution2D::new(&weighted, width, height),
+            mean_luminance
+        }
+    }
+
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+
+    pub fn set_rotation(&mut self, rotation_degrees: f64) {
+        self.rotation = degrees_to_radians(rotation_degrees);
+    }
+
+    fn rotate(&self, direction: Vec3, angle: f64) -> Vec3 {
+        let (sin, cos) = f64::sin_cos(angle);
+        Vec3::new(cos * direction.x() + sin * direction.z(), direction.y(), -sin * direction.x() + cos * direction.z())
+    }
+
+    // world direction to image coordinates in [0, 1)^2, v running down from the top
+    fn direction_to_uv(&self, direction: Vec3) -> (f64, f64) {
+        let d = unit_vector(self.rotate(direction, -self.rotation));
+        let u = 0.5 + f64::atan2(d.x(), -d.z()) / (2.0 * std::f64::consts::PI);
+        let v = f64::acos(f64::clamp(d.y(), -1.0, 1.0)) / std::f64::consts::PI;
+
+        (u, v)
+    }
+
+    fn uv_to_direction(&self, u: f64, v: f64) -> Vec3 {
+        let phi = 2.0 * std::f64::consts::PI * (u - 0.5);
+        let theta = std::f64::consts::PI * v;
+        let d = Vec3::new(f64::sin(theta) * f64::sin(phi), f64::cos(theta), -f64::sin(theta) * f64::cos(phi));
+
+        self.rotate(d, self.rotation)
+    }
+
+    // converts a density over the image to one over solid angle
+    fn solid_angle_pdf(pdf: f64, v: f64) -> f64 {
+        let sin_theta = f64::sin(std::f64::consts::PI * v);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        pdf / (2.0 * std::f64::consts::PI * std::f64::consts::PI * sin_theta)
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn color(&self, direction: Vec3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let x = usize::min((u * self.width as f64) as usize, self.width - 1);
+        let y = usize::min((v * self.height as f64) as usize, self.height - 1);
+
+        self.intensity * self.pixels[y * self.width + x]
+    }
+}
+
+// As a light the environment samples bright texels more often
+impl Light for EnvironmentMap {
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let ((u, v), _) = self.distribution.sample(random_double(), random_double());
+        let direction = self.uv_to_direction(u, v);
+
+        Some(LightSample {
+            direction,
+            radiance: self.color(direction),
+            pdf: self.pdf_li(point, direction, time),
+            distance: f64::INFINITY
+        })
+    }
+
+    fn pdf_li(&self, _point: Point3, direction: Vec3, _time: f64) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        EnvironmentMap::solid_angle_pdf(self.distribution.pdf(u, v), v)
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn power(&self) -> f64 {
+        std::f64::consts::PI * self.intensity * self.mean_luminance
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_images_are_errors() {
+        assert!(EnvironmentMap::new("assets/no_such_map.hdr", 1.0, 0.0).is_err());
+        assert!(EnvironmentMap::new("assets/downlight.ies", 1.0, 0.0).is_err());
+        assert!(EnvironmentMap::new("assets/earthmap.jpg", 1.0, 0.0).is_ok());
+    }
+}
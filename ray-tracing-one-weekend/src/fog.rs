@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::{color::Color, common::random_double, material::{Isotropic, Material}, ray::Ray};
+
+// A participating medium filling the whole scene, with density falling off exponentially
+// above base_height (a falloff of zero gives homogeneous fog).
+pub struct Fog {
+    density: f64,
+    falloff: f64,
+    base_height: f64,
+    phase_function: Arc<dyn Material>
+}
+
+impl Fog {
+    pub fn new(density: f64, falloff: f64, base_height: f64, phase_function: Arc<dyn Material>) -> Self {
+        Fog {
+            density,
+            falloff,
+            base_height,
+            phase_function
+        }
+    }
+
+    pub fn homogeneous(density: f64, albedo: Color) -> Self {
+        Fog::new(density, 0.0, 0.0, Arc::new(Isotropic::from_color(albedo)))
+    }
+
+    pub fn height_exponential(density: f64, falloff: f64, base_height: f64, albedo: Color) -> Self {
+        Fog::new(density, falloff, base_height, Arc::new(Isotropic::from_color(albedo)))
+    }
+
+    pub fn phase_function(&self) -> Arc<dyn Material> {
+        self.phase_function.clone()
+    }
+
+    // samples where along the ray the fog scatters it, None if it gets past t_max
+    pub fn sample(&self, ray: &Ray, t_max: f64) -> Option<f64> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        // optical depth to t is density_at_origin * (1 - e^(-rate * t)) / rate, inverted for t
+        let optical_depth = -f64::ln(random_double());
+        let density_at_origin = self.density * f64::exp(-self.falloff * (ray.origin().y() - self.base_height)) * ray.direction().length();
+        let rate = self.falloff * ray.direction().y();
+
+        let t = if f64::abs(rate) < 1e-9 {
+            optical_depth / density_at_origin
+        } else {
+            let remaining = 1.0 - optical_depth * rate / density_at_origin;
+            if remaining <= 0.0 {
+                return None;
+            }
+            -f64::ln(remaining) / rate
+        };
+
+        if t < t_max {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{material::Material, ray::Ray, vec3::{self, Point3, Vec3}};
+use crate::{aabb::AABB, light::LightList, material::Material, ray::Ray, vec3::{self, Point3, Vec3}};
 
 #[derive(Clone)]
 pub struct HitRecord {
@@ -27,11 +27,19 @@ impl HitRecord {
 pub trait Hittable: Send + Sync{
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
 
-    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
-        0.0
+    // fraction of light getting through along the ray between t_min and t_max, for shadow rays;
+    // anything the ray hits blocks it, media can let some through
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if self.hit(ray, t_min, t_max).is_some() {0.0} else {1.0}
     }
 
-    fn random(&self, origin: Point3) -> Vec3 {
-        Vec3::new(1.0, 0.0, 0.0)
+    // a box the object stays inside over the whole shutter interval, so rays missing it can be
+    // skipped; None when it has no bounds, like a medium with an open boundary, or they aren't known
+    fn bounding_box(&self) -> Option<AABB> {
+        None
     }
+
+    // adds whatever parts of this object emit light and can be sampled as lights, with no default
+    // so that wrappers can't forget to pass their contents' lights on
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList);
 }
\ No newline at end of file
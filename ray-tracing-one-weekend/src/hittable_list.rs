@@ -1,10 +1,12 @@
 use std::sync::Arc;
 
-use crate::{common::random_int_range, hittable::{HitRecord, Hittable}, vec3::{Point3, Vec3}};
+use crate::{aabb::AABB, hittable::{HitRecord, Hittable}, light::LightList};
 
 #[derive(Default)]
 pub struct HittableList {
-    objects: Vec<Arc<dyn Hittable>>
+    objects: Vec<Arc<dyn Hittable>>,
+    // objects whose emission is left for scattered rays to find rather than sampled
+    unsampled: Vec<bool>
 }
 
 impl HittableList {
@@ -14,6 +16,25 @@ impl HittableList {
 
     pub fn add(&mut self, object: Arc<dyn Hittable>) {
         self.objects.push(object);
+        self.unsampled.push(false);
+    }
+
+    // for emitters too dim or too large to be worth sampling directly
+    pub fn add_without_light_sampling(&mut self, object: Arc<dyn Hittable>) {
+        self.objects.push(object);
+        self.unsampled.push(true);
+    }
+
+    // every emissive object in the list, to pass to the camera as its lights
+    pub fn lights(&self) -> LightList {
+        let mut lights = LightList::new();
+        for (object, unsampled) in self.objects.iter().zip(&self.unsampled) {
+            if !unsampled {
+                object.clone().collect_lights(&mut lights);
+            }
+        }
+
+        lights
     }
 }
 
@@ -32,15 +53,30 @@ impl Hittable for HittableList {
         temp_rec
     }
 
-    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
-        let weight = 1.0 / self.objects.len() as f64;
-        self.objects.iter()
-            .map(|object| weight * object.pdf_value(origin, direction))
-            .sum()
+    fn transmittance(&self, ray: &crate::ray::Ray, t_min: f64, t_max: f64) -> f64 {
+        let mut transmittance = 1.0;
+        for object in &self.objects {
+            transmittance *= object.transmittance(ray, t_min, t_max);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+
+        transmittance
+    }
+
+    // unbounded as soon as any object is
+    fn bounding_box(&self) -> Option<AABB> {
+        let mut boxes = self.objects.iter().map(|object| object.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |bounds, object_box| Some(AABB::from_sub_aabbs(bounds, object_box?)))
     }
-    
-    fn random(&self, origin: Point3) -> Vec3 {
-        let index: usize = random_int_range(0, (self.objects.len() - 1) as i32) as usize;
-        self.objects[index].random(origin)
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        for (object, unsampled) in self.objects.iter().zip(&self.unsampled) {
+            if !unsampled {
+                object.clone().collect_lights(lights);
+            }
+        }
     }
 }
\ No newline at end of file
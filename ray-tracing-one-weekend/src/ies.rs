@@ -0,0 +1,187 @@
+use std::fs;
+
+// A luminaire's intensity distribution read from an IESNA LM-63 photometric file.
+//
+// Only type C photometry is handled, which is what nearly every file uses: vertical angles
+// run from 0 straight down the luminaire's axis to 180 straight up, and horizontal angles
+// go around it. Intensities are kept relative to the brightest direction.
+pub struct IesProfile {
+    vertical_angles: Vec<f64>,
+    horizontal_angles: Vec<f64>,
+    // one row of vertical samples per horizontal angle
+    candela: Vec<Vec<f64>>
+}
+
+impl IesProfile {
+    pub fn new(ies_file: &str) -> Result<IesProfile, String> {
+        let text = fs::read_to_string(ies_file).map_err(|e| format!("couldn't read ies file {}: {}", ies_file, e))?;
+        IesProfile::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<IesProfile, String> {
+        let mut lines = text.lines();
+
+        // keyword lines up to the tilt specification
+        let tilt = loop {
+            let line = lines.next().ok_or("ies file has no TILT line")?;
+            if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                break tilt.trim().to_string();
+            }
+        };
+
+        let numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<f64>().ok().filter(|n| n.is_finite()).ok_or(format!("ies data should be numeric, found {}", token)))
+            .collect::<Result<Vec<f64>, String>>()?;
+        let mut numbers = numbers.as_slice();
+
+        // tilt only matters for lamps mounted at an angle, so skip over it
+        if tilt == "INCLUDE" {
+            // lamp to luminaire geometry, then the number of angle and factor pairs
+            let tilt_header = take(&mut numbers, 2)?;
+            let pairs = count(tilt_header[1])?;
+            take(&mut numbers, pairs.checked_mul(2).ok_or("ies tilt table is too long")?)?;
+        }
+
+        // lamps, lumens per lamp, multiplier, vertical and horizontal angle counts, photometric type,
+        // units, luminous opening width, length and height, ballast factor, future use and input watts
+        let header = take(&mut numbers, 13)?;
+        let multiplier = header[2];
+        let vertical_count = count(header[3])?;
+        let horizontal_count = count(header[4])?;
+        let photometric_type = header[5];
+        if photometric_type != 1.0 {
+            return Err(format!("only type C ies photometry is supported, not type {}", photometric_type));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(format!("ies file has {} vertical and {} horizontal angles", vertical_count, horizontal_count));
+        }
+
+        let vertical_angles = take(&mut numbers, vertical_count)?.to_vec();
+        let horizontal_angles = take(&mut numbers, horizontal_count)?.to_vec();
+        // lookups bracket angles by binary search
+        for angles in [&vertical_angles, &horizontal_angles] {
+            if !angles.windows(2).all(|pair| pair[0] <= pair[1]) {
+                return Err(format!("ies angles should be in increasing order, not {:?}", angles));
+            }
+        }
+
+        let candela_count = vertical_count.checked_mul(horizontal_count).ok_or("ies file has too many angles")?;
+        let candela: Vec<Vec<f64>> = take(&mut numbers, candela_count)?
+            .chunks(vertical_count)
+            .map(|row| row.iter().map(|c| multiplier * c).collect())
+            .collect();
+
+        let max = candela.iter().flatten().cloned().fold(0.0, f64::max);
+        let candela = candela.into_iter()
+            .map(|row| row.into_iter().map(|c| if max > 0.0 {c / max} else {0.0}).collect())
+            .collect();
+
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela
+        })
+    }
+
+    // relative intensity, peaking at 1, at an angle from the axis (by its cosine) and around it (radians)
+    pub fn value(&self, cos_theta: f64, phi: f64) -> f64 {
+        let theta = f64::acos(f64::clamp(cos_theta, -1.0, 1.0)).to_degrees();
+        let (v0, v1, tv) = match Self::bracket(&self.vertical_angles, theta) {
+            Some(bracket) => bracket,
+            None => return 0.0
+        };
+
+        let lerp_row = |row: &Vec<f64>| (1.0 - tv) * row[v0] + tv * row[v1];
+        if self.horizontal_angles.len() == 1 {
+            return lerp_row(&self.candela[0]);
+        }
+
+        // fold the azimuth into the range the file covers, by its symmetry
+        let mut phi = phi.to_degrees().rem_euclid(360.0);
+        let last = *self.horizontal_angles.last().expect("ies file should have horizontal angles");
+        if last <= 90.0 {
+            if phi > 180.0 {phi = 360.0 - phi;}
+            if phi > 90.0 {phi = 180.0 - phi;}
+        } else if last <= 180.0 && phi > 180.0 {
+            phi = 360.0 - phi;
+        }
+
+        match Self::bracket(&self.horizontal_angles, phi) {
+            Some((h0, h1, th)) => (1.0 - th) * lerp_row(&self.candela[h0]) + th * lerp_row(&self.candela[h1]),
+            None => 0.0
+        }
+    }
+
+    // the widest vertical angle, in degrees, that any light leaves at
+    pub fn max_angle(&self) -> f64 {
+        self.vertical_angles.iter().enumerate()
+            .filter(|&(i, _)| self.candela.iter().any(|row| row[i] > 0.0))
+            .map(|(_, &angle)| angle)
+            .fold(0.0, f64::max)
+    }
+
+    // indices of the samples either side of x in a sorted list and how far x is between them
+    fn bracket(angles: &[f64], x: f64) -> Option<(usize, usize, f64)> {
+        let first = *angles.first()?;
+        let last = *angles.last()?;
+        if x < first || x > last {
+            return None;
+        }
+        if angles.len() == 1 {
+            return Some((0, 0, 0.0));
+        }
+
+        let upper = usize::clamp(angles.partition_point(|&a| a <= x), 1, angles.len() - 1);
+        let lower = upper - 1;
+        let span = angles[upper] - angles[lower];
+        let t = if span > 0.0 {(x - angles[lower]) / span} else {0.0};
+
+        Some((lower, upper, f64::clamp(t, 0.0, 1.0)))
+    }
+}
+
+// the next n numbers, as long as the file has that many left
+fn take<'a>(numbers: &mut &'a [f64], n: usize) -> Result<&'a [f64], String> {
+    if n > numbers.len() {
+        return Err(format!("ies file ends early, wanting {} more numbers but having {}", n, numbers.len()));
+    }
+
+    let (taken, rest) = numbers.split_at(n);
+    *numbers = rest;
+    Ok(taken)
+}
+
+fn count(number: f64) -> Result<usize, String> {
+    if number >= 0.0 && number.fract() == 0.0 && number <= usize::MAX as f64 {
+        Ok(number as usize)
+    } else {
+        Err(format!("ies count should be a whole number, not {}", number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002\nTILT=NONE\n1 1000 1 3 1 1 2 0.1 0.1 0.0\n1.0 1.0 12\n0 45 90\n0\n100 50 0\n";
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(IesProfile::parse("IESNA:LM-63-2002\n1 1000 1 3 1 1 2").is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("100 50 0", "100 50")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("3 1 1 2", "3 1 3 2")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("3 1 1 2", "0 1 1 2")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("100", "bright")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("1 1000 1 3 1", "1 1000 1 3 1e18")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("1 1000 1 3 1", "1 1000 1 3 -2")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("0 45 90", "0 90 45")).is_err());
+        assert!(IesProfile::parse(&DOWNLIGHT.replace("TILT=NONE", "TILT=INCLUDE\n1 1e19")).is_err());
+
+        let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+        assert_eq!(profile.value(1.0, 0.0), 1.0);
+        assert_eq!(profile.max_angle(), 45.0);
+        assert!(IesProfile::new("assets/downlight.ies").is_ok());
+    }
+}
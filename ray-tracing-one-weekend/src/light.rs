@@ -0,0 +1,441 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{color::Color, common::{degrees_to_radians, random_double, random_int_range}, distribution::Distribution1D, hittable::{HitRecord, Hittable}, light_bvh::{LightBounds, LightBvh}, material::Material, onb::Onb, ray::Ray, vec3::{dot, unit_vector, Point3, Vec3}};
+
+// Incident light arriving at a point from a light source
+pub struct LightSample {
+    // unit vector from the point towards the light
+    pub direction: Vec3,
+    pub radiance: Color,
+    // density of having picked this direction, 1 for delta lights
+    pub pdf: f64,
+    // how far a shadow ray has to get to reach the light
+    pub distance: f64
+}
+
+pub trait Light: Send + Sync {
+    // picks a direction from point towards the light and the light arriving along it, with
+    // lights that move taken where they are at time
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample>;
+    // solid angle density of sample_li choosing direction from point, zero for delta lights
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64;
+    // delta lights can't be hit by rays and so are only ever reached by sampling them
+    fn is_delta(&self) -> bool;
+    // total emitted power as luminance; lights without extent give it per unit area instead
+    fn power(&self) -> f64;
+    // None for lights that are everywhere at once, like the sky
+    fn bounds(&self) -> Option<LightBounds>;
+}
+
+pub fn luminance(c: Color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+// Power of an emitter estimated from what its material emits at a few (point, outward
+// normal, u, v) spots spread over its surface, each seen from a spread of directions on
+// both sides so that emission profiles are accounted for.
+pub fn emitted_power(mat: &Arc<dyn Material>, area: f64, spots: &[(Point3, Vec3, f64, f64)]) -> f64 {
+    const STRATA: usize = 4;
+
+    let mut total = 0.0;
+    for &(p, normal, u, v) in spots {
+        let uvw = Onb::new(&normal);
+        for front_face in [true, false] {
+            for i in 0..STRATA {
+                for j in 0..STRATA {
+                    // cosine distributed directions leaving the surface
+                    let r1 = (i as f64 + 0.5) / STRATA as f64;
+                    let r2 = (j as f64 + 0.5) / STRATA as f64;
+                    let phi = 2.0 * std::f64::consts::PI * r1;
+                    let sin_theta = f64::sqrt(r2);
+                    let local = Vec3::new(f64::cos(phi) * sin_theta, f64::sin(phi) * sin_theta, f64::sqrt(1.0 - r2));
+                    let outgoing = if front_face {uvw.transform(local)} else {-uvw.transform(local)};
+
+                    let r_in = Ray::new(p + outgoing, -outgoing, 0.0);
+                    let rec = HitRecord {
+                        p,
+                        normal: if front_face {normal} else {-normal},
+                        mat: mat.clone(),
+                        t: 1.0,
+                        u,
+                        v,
+                        front_face
+                    };
+                    total += luminance(mat.emitted(&r_in, &rec, u, v, &p));
+                }
+            }
+        }
+    }
+
+    std::f64::consts::PI * area * total / (spots.len() * STRATA * STRATA) as f64
+}
+
+// Builds the sample for a direction towards an emitting shape by tracing to it, taking the
+// radiance from whatever its material emits there.
+pub fn sample_emitter(emitter: &dyn Hittable, point: Point3, direction: Vec3, time: f64, pdf: f64) -> Option<LightSample> {
+    let direction = unit_vector(direction);
+    let ray = Ray::new(point, direction, time);
+    let rec = emitter.hit(&ray, 0.001, f64::INFINITY)?;
+
+    Some(LightSample {
+        direction,
+        radiance: rec.mat.emitted(&ray, &rec, rec.u, rec.v, &rec.p),
+        pdf,
+        distance: rec.t
+    })
+}
+
+pub struct PointLight {
+    position: Point3,
+    intensity: Color
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> PointLight {
+        PointLight {
+            position,
+            intensity
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, point: Point3, _time: f64) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.length();
+
+        Some(LightSample {
+            direction: to_light / distance,
+            radiance: self.intensity / (distance * distance),
+            pdf: 1.0,
+            distance
+        })
+    }
+
+    fn pdf_li(&self, _point: Point3, _direction: Vec3, _time: f64) -> f64 {
+        0.0
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self) -> f64 {
+        4.0 * std::f64::consts::PI * luminance(self.intensity)
+    }
+
+    // delta lights are always sampled, so never need choosing between
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+pub struct SpotLight {
+    position: Point3,
+    direction: Vec3,
+    intensity: Color,
+    cos_total_width: f64,
+    cos_falloff_start: f64
+}
+
+impl SpotLight {
+    // full intensity inside falloff_start, fading smoothly to nothing at total_width (half angles in degrees)
+    pub fn new(position: Point3, direction: Vec3, intensity: Color, total_width: f64, falloff_start: f64) -> SpotLight {
+        SpotLight {
+            position,
+            direction: unit_vector(direction),
+            intensity,
+            cos_total_width: f64::cos(degrees_to_radians(total_width)),
+            cos_falloff_start: f64::cos(degrees_to_radians(f64::min(falloff_start, total_width)))
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta <= self.cos_total_width {
+            return 0.0;
+        }
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+
+        let t = (cos_theta - self.cos_total_width) / (self.cos_falloff_start - self.cos_total_width);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, point: Point3, _time: f64) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.length();
+        let direction = to_light / distance;
+
+        let falloff = self.falloff(dot(-direction, self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            radiance: falloff * self.intensity / (distance * distance),
+            pdf: 1.0,
+            distance
+        })
+    }
+
+    fn pdf_li(&self, _point: Point3, _direction: Vec3, _time: f64) -> f64 {
+        0.0
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self) -> f64 {
+        2.0 * std::f64::consts::PI * luminance(self.intensity) * (1.0 - 0.5 * (self.cos_falloff_start + self.cos_total_width))
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+// Light from very far away arriving along one direction, like the sun
+pub struct DirectionalLight {
+    direction: Vec3,
+    irradiance: Color
+}
+
+impl DirectionalLight {
+    // direction is the way the light travels
+    pub fn new(direction: Vec3, irradiance: Color) -> DirectionalLight {
+        DirectionalLight {
+            direction: unit_vector(direction),
+            irradiance
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _point: Point3, _time: f64) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            radiance: self.irradiance,
+            pdf: 1.0,
+            distance: f64::INFINITY
+        })
+    }
+
+    fn pdf_li(&self, _point: Point3, _direction: Vec3, _time: f64) -> f64 {
+        0.0
+    }
+
+    fn is_delta(&self) -> bool {
+        true
+    }
+
+    fn power(&self) -> f64 {
+        std::f64::consts::PI * luminance(self.irradiance)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
+
+// How area lights are chosen between when sampling one
+#[derive(Clone, Copy, Default)]
+pub enum LightSelection {
+    Uniform,
+    // in proportion to emitted power
+    #[default]
+    Power,
+    // with a light BVH, favouring lights close to and facing the shading point
+    Bvh
+}
+
+// All the lights in a scene, kept apart by whether they can be hit or only sampled
+#[derive(Default)]
+pub struct LightList {
+    delta_lights: Vec<Arc<dyn Light>>,
+    // area and infinite lights, the ones scattered rays can also find by chance
+    area_lights: Vec<Arc<dyn Light>>,
+    selection: LightSelection,
+    // built from the area lights the first time one is sampled
+    sampler: OnceLock<LightSampler>
+}
+
+impl LightList {
+    pub fn new() -> LightList {
+        Default::default()
+    }
+
+    pub fn add(&mut self, light: Arc<dyn Light>) {
+        if light.is_delta() {
+            self.delta_lights.push(light);
+        } else {
+            self.area_lights.push(light);
+            self.sampler = OnceLock::new();
+        }
+    }
+
+    pub fn set_selection(&mut self, selection: LightSelection) {
+        self.selection = selection;
+        self.sampler = OnceLock::new();
+    }
+
+    pub fn delta_lights(&self) -> &[Arc<dyn Light>] {
+        &self.delta_lights
+    }
+
+    pub fn area_lights(&self) -> &[Arc<dyn Light>] {
+        &self.area_lights
+    }
+
+    fn sampler(&self) -> &LightSampler {
+        self.sampler.get_or_init(|| LightSampler::new(&self.area_lights, self.selection))
+    }
+
+    // whether there is any area light worth sampling from point
+    pub fn illuminates(&self, point: Point3) -> bool {
+        self.sampler().choices(point) > 0
+    }
+
+    // samples one of the area lights at time, with the density over all of them
+    pub fn sample_area_light(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let (index, _) = self.sampler().sample(point)?;
+        let mut light_sample = self.area_lights[index].sample_li(point, time)?;
+        light_sample.pdf = self.area_light_pdf(point, light_sample.direction, time);
+
+        Some(light_sample)
+    }
+
+    // density of sample_area_light choosing direction, over every light that could have
+    pub fn area_light_pdf(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let sampler = self.sampler();
+        self.area_lights.iter().enumerate()
+            .map(|(index, light)| {
+                let pdf = light.pdf_li(point, direction, time);
+                if pdf > 0.0 {sampler.pmf(point, index) * pdf} else {0.0}
+            })
+            .sum()
+    }
+}
+
+enum Slot {
+    Infinite,
+    // index among the bounded lights
+    Bounded(usize)
+}
+
+enum BoundedSelection {
+    Uniform,
+    Power(Distribution1D),
+    Bvh(LightBvh)
+}
+
+// Lights without bounds, like the sky, can't be compared with the rest by power or distance,
+// so as in PBRT each gets an even share alongside the bounded lights taken together.
+struct LightSampler {
+    slots: Vec<Slot>,
+    infinite: Vec<usize>,
+    bounded: Vec<usize>,
+    selection: BoundedSelection
+}
+
+impl LightSampler {
+    fn new(lights: &[Arc<dyn Light>], selection: LightSelection) -> LightSampler {
+        let mut slots = Vec::new();
+        let mut infinite = Vec::new();
+        let mut bounded = Vec::new();
+        let mut bounds = Vec::new();
+        for (index, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(light_bounds) => {
+                    slots.push(Slot::Bounded(bounded.len()));
+                    bounded.push(index);
+                    bounds.push(light_bounds);
+                },
+                None => {
+                    slots.push(Slot::Infinite);
+                    infinite.push(index);
+                }
+            }
+        }
+
+        // without any power to go on neither power nor a bvh would tell the lights apart, so say so
+        // rather than quietly sampling them uniformly
+        let has_power = bounds.iter().any(|b| b.phi > 0.0);
+        let selection = match selection {
+            LightSelection::Uniform => BoundedSelection::Uniform,
+            LightSelection::Power | LightSelection::Bvh if !bounded.is_empty() && !has_power => {
+                eprintln!("Warning: area lights report no power, choosing between them uniformly.");
+                BoundedSelection::Uniform
+            },
+            LightSelection::Power => BoundedSelection::Power(Distribution1D::new(bounded.iter().map(|&i| lights[i].power()).collect())),
+            LightSelection::Bvh if has_power => BoundedSelection::Bvh(LightBvh::new(bounds)),
+            LightSelection::Bvh => BoundedSelection::Uniform
+        };
+
+        LightSampler {
+            slots,
+            infinite,
+            bounded,
+            selection
+        }
+    }
+
+    fn bounded_reach(&self, point: Point3) -> bool {
+        match &self.selection {
+            BoundedSelection::Bvh(bvh) => bvh.reaches(point),
+            _ => !self.bounded.is_empty()
+        }
+    }
+
+    // the infinite lights plus the bounded ones as a single choice, if they could light point
+    fn choices(&self, point: Point3) -> usize {
+        self.infinite.len() + self.bounded_reach(point) as usize
+    }
+
+    fn sample(&self, point: Point3) -> Option<(usize, f64)> {
+        let choices = self.choices(point);
+        if choices == 0 {
+            return None;
+        }
+
+        let choice = random_int_range(0, choices as i32 - 1) as usize;
+        if choice < self.infinite.len() {
+            return Some((self.infinite[choice], 1.0 / choices as f64));
+        }
+
+        let (bounded_index, pmf) = match &self.selection {
+            BoundedSelection::Uniform => (random_int_range(0, self.bounded.len() as i32 - 1) as usize, 1.0 / self.bounded.len() as f64),
+            BoundedSelection::Power(distribution) => {
+                let (_, _, offset) = distribution.sample(random_double());
+                (offset, distribution.pmf(offset))
+            },
+            BoundedSelection::Bvh(bvh) => bvh.sample(point)?
+        };
+
+        Some((self.bounded[bounded_index], pmf / choices as f64))
+    }
+
+    fn pmf(&self, point: Point3, index: usize) -> f64 {
+        let choices = self.choices(point);
+        if choices == 0 {
+            return 0.0;
+        }
+
+        let bounded_index = match self.slots[index] {
+            Slot::Infinite => return 1.0 / choices as f64,
+            Slot::Bounded(bounded_index) => bounded_index
+        };
+
+        let pmf = match &self.selection {
+            BoundedSelection::Uniform => 1.0 / self.bounded.len() as f64,
+            BoundedSelection::Power(distribution) => distribution.pmf(bounded_index),
+            BoundedSelection::Bvh(bvh) => bvh.pmf(point, bounded_index)
+        };
+
+        pmf / choices as f64
+    }
+}
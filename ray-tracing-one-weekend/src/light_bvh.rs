@@ -0,0 +1,264 @@
+use crate::{aabb::AABB, common::random_double, vec3::{cross, dot, unit_vector, Point3, Vec3}};
+
+// Where a light is and which way it shines, loose enough to cover a whole group of lights.
+#[derive(Clone, Copy)]
+pub struct LightBounds {
+    pub bounds: AABB,
+    // the normals of the emitters lie within acos(cos_theta_o) of direction, and light
+    // leaves each of them up to a further acos(cos_theta_e) away from its normal
+    pub direction: Vec3,
+    pub cos_theta_o: f64,
+    pub cos_theta_e: f64,
+    pub phi: f64,
+    pub two_sided: bool
+}
+
+impl LightBounds {
+    pub fn new(bounds: AABB, direction: Vec3, cos_theta_o: f64, cos_theta_e: f64, phi: f64, two_sided: bool) -> LightBounds {
+        LightBounds {
+            bounds,
+            direction: unit_vector(direction),
+            cos_theta_o,
+            cos_theta_e,
+            phi,
+            two_sided
+        }
+    }
+
+    pub fn union(a: &LightBounds, b: &LightBounds) -> LightBounds {
+        if a.phi <= 0.0 {
+            return *b;
+        }
+        if b.phi <= 0.0 {
+            return *a;
+        }
+
+        let (direction, cos_theta_o) = cone_union(a.direction, a.cos_theta_o, b.direction, b.cos_theta_o);
+
+        LightBounds {
+            bounds: AABB::from_sub_aabbs(a.bounds, b.bounds),
+            direction,
+            cos_theta_o,
+            cos_theta_e: f64::min(a.cos_theta_e, b.cos_theta_e),
+            phi: a.phi + b.phi,
+            two_sided: a.two_sided || b.two_sided
+        }
+    }
+
+    // Conservative estimate of how much these lights contribute at point, following PBRT's
+    // light BVH: power over squared distance, zero when point is outside every emission cone.
+    pub fn importance(&self, point: Point3) -> f64 {
+        if self.phi <= 0.0 {
+            return 0.0;
+        }
+
+        let centroid = self.bounds.centroid();
+        let radius = 0.5 * self.bounds.diagonal().length();
+        let distance_squared = (point - centroid).length_squared();
+        if self.bounds.contains(point) || distance_squared <= radius * radius {
+            return self.phi / f64::max(distance_squared, radius * radius);
+        }
+
+        let cos_sub_clamped = |sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64| {
+            if cos_a > cos_b {1.0} else {cos_a * cos_b + sin_a * sin_b}
+        };
+        let sin_sub_clamped = |sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64| {
+            if cos_a > cos_b {0.0} else {sin_a * cos_b - cos_a * sin_b}
+        };
+        let sin_from_cos = |cos: f64| f64::sqrt(f64::max(0.0, 1.0 - cos * cos));
+
+        let to_point = unit_vector(point - centroid);
+        let mut cos_theta_w = dot(self.direction, to_point);
+        if self.two_sided {
+            cos_theta_w = f64::abs(cos_theta_w);
+        }
+        let sin_theta_w = sin_from_cos(cos_theta_w);
+
+        // the angle the bounds' bounding sphere takes up as seen from point
+        let cos_theta_b = f64::sqrt(1.0 - radius * radius / distance_squared);
+        let sin_theta_b = sin_from_cos(cos_theta_b);
+
+        let sin_theta_o = sin_from_cos(self.cos_theta_o);
+        let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+
+        self.phi * cos_theta_p / distance_squared
+    }
+}
+
+// smallest cone around both cones, given by their axes and the cosines of their half angles
+fn cone_union(a: Vec3, cos_a: f64, b: Vec3, cos_b: f64) -> (Vec3, f64) {
+    let theta_a = f64::acos(f64::clamp(cos_a, -1.0, 1.0));
+    let theta_b = f64::acos(f64::clamp(cos_b, -1.0, 1.0));
+    let theta_d = f64::acos(f64::clamp(dot(a, b), -1.0, 1.0));
+
+    if f64::min(theta_d + theta_b, std::f64::consts::PI) <= theta_a {
+        return (a, cos_a);
+    }
+    if f64::min(theta_d + theta_a, std::f64::consts::PI) <= theta_b {
+        return (b, cos_b);
+    }
+
+    let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+    let axis = cross(a, b);
+    if theta_o >= std::f64::consts::PI || axis.length_squared() == 0.0 {
+        return (a, -1.0);
+    }
+
+    // rotate a towards b about their common normal (Rodrigues' formula)
+    let k = unit_vector(axis);
+    let theta_r = theta_o - theta_a;
+    let (sin_r, cos_r) = f64::sin_cos(theta_r);
+    let direction = a * cos_r + cross(k, a) * sin_r + k * dot(k, a) * (1.0 - cos_r);
+
+    (unit_vector(direction), f64::cos(theta_o))
+}
+
+enum LightBvhNode {
+    // the first child directly follows its parent
+    Interior { bounds: LightBounds, second_child: usize },
+    Leaf { bounds: LightBounds, light: usize }
+}
+
+impl LightBvhNode {
+    fn bounds(&self) -> &LightBounds {
+        match self {
+            LightBvhNode::Interior { bounds, .. } => bounds,
+            LightBvhNode::Leaf { bounds, .. } => bounds
+        }
+    }
+}
+
+// Picks lights by walking down a tree of their bounds, at each node choosing a child in
+// proportion to its importance at the shading point.
+pub struct LightBvh {
+    nodes: Vec<LightBvhNode>,
+    // the path from the root to each light, one bit per level with 1 for a second child
+    trails: Vec<u64>
+}
+
+impl LightBvh {
+    pub fn new(bounds: Vec<LightBounds>) -> LightBvh {
+        let mut bvh = LightBvh {
+            nodes: Vec::new(),
+            trails: vec![0; bounds.len()]
+        };
+
+        let mut lights: Vec<(usize, LightBounds)> = bounds.into_iter().enumerate().collect();
+        if !lights.is_empty() {
+            bvh.build(&mut lights, 0, 0);
+        }
+
+        bvh
+    }
+
+    fn build(&mut self, lights: &mut [(usize, LightBounds)], trail: u64, depth: u32) -> usize {
+        let index = self.nodes.len();
+        if lights.len() == 1 {
+            let (light, bounds) = lights[0];
+            self.nodes.push(LightBvhNode::Leaf { bounds, light });
+            self.trails[light] = trail;
+            return index;
+        }
+        assert!(depth < 64, "light bvh should fit its trails in 64 bits");
+
+        // split at the median centroid along the axis the centroids spread furthest on
+        let centroids = lights.iter()
+            .map(|(_, bounds)| AABB::from_points(bounds.bounds.centroid(), bounds.bounds.centroid()))
+            .reduce(AABB::from_sub_aabbs)
+            .expect("should have lights to split");
+        let axis = centroids.longest_axis();
+        let key = |bounds: &LightBounds| {
+            let c = bounds.bounds.centroid();
+            match axis {
+                0 => c.x(),
+                1 => c.y(),
+                _ => c.z()
+            }
+        };
+        lights.sort_by(|a, b| key(&a.1).total_cmp(&key(&b.1)));
+
+        let bounds = lights.iter().skip(1).fold(lights[0].1, |acc, (_, b)| LightBounds::union(&acc, b));
+        self.nodes.push(LightBvhNode::Interior { bounds, second_child: 0 });
+
+        let (first, second) = lights.split_at_mut(lights.len() / 2);
+        self.build(first, trail, depth + 1);
+        let second_child = self.build(second, trail | (1 << depth), depth + 1);
+        self.nodes[index] = LightBvhNode::Interior { bounds, second_child };
+
+        index
+    }
+
+    // whether any light might reach point at all
+    pub fn reaches(&self, point: Point3) -> bool {
+        !self.nodes.is_empty() && self.nodes[0].bounds().importance(point) > 0.0
+    }
+
+    // returns the index of the chosen light and the probability of choosing it
+    pub fn sample(&self, point: Point3) -> Option<(usize, f64)> {
+        if !self.reaches(point) {
+            return None;
+        }
+
+        let mut index = 0;
+        let mut pmf = 1.0;
+        loop {
+            match self.nodes[index] {
+                LightBvhNode::Leaf { light, .. } => return Some((light, pmf)),
+                LightBvhNode::Interior { second_child, .. } => {
+                    let (p_first, p_second) = self.child_probabilities(index, second_child, point)?;
+                    if random_double() < p_first {
+                        index += 1;
+                        pmf *= p_first;
+                    } else {
+                        index = second_child;
+                        pmf *= p_second;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn pmf(&self, point: Point3, light: usize) -> f64 {
+        if !self.reaches(point) {
+            return 0.0;
+        }
+
+        let mut trail = self.trails[light];
+        let mut index = 0;
+        let mut pmf = 1.0;
+        loop {
+            match self.nodes[index] {
+                LightBvhNode::Leaf { .. } => return pmf,
+                LightBvhNode::Interior { second_child, .. } => {
+                    let (p_first, p_second) = match self.child_probabilities(index, second_child, point) {
+                        Some(probabilities) => probabilities,
+                        None => return 0.0
+                    };
+                    if trail & 1 == 0 {
+                        index += 1;
+                        pmf *= p_first;
+                    } else {
+                        index = second_child;
+                        pmf *= p_second;
+                    }
+                    trail >>= 1;
+                }
+            }
+        }
+    }
+
+    fn child_probabilities(&self, index: usize, second_child: usize, point: Point3) -> Option<(f64, f64)> {
+        let first = self.nodes[index + 1].bounds().importance(point);
+        let second = self.nodes[second_child].bounds().importance(point);
+        if first + second <= 0.0 {
+            return None;
+        }
+
+        Some((first / (first + second), second / (first + second)))
+    }
+}
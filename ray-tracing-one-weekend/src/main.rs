@@ -1,18 +1,28 @@
 use std::sync::Arc;
 
+use blackbody::LuminousPower;
 use camera::Camera;
 use color::Color;
 use common::{random_double, random_double_range};
 use constant_medium::ConstantMedium;
+use environment_map::EnvironmentMap;
+use heterogeneous_medium::{HeterogeneousMedium, NoiseDensity};
+use hittable::Hittable;
 use hittable_list::HittableList;
-use material::{Dielectric, DiffuseLight, Empty, Lambertian, Metal};
+use ies::IesProfile;
+use light::{DirectionalLight, LightSelection, PointLight, SpotLight};
+use mat4::Mat4;
+use material::{Dielectric, DiffuseLight, EmissionProfile, Empty, Lambertian, Metal};
 use noise_texture::NoiseTexture;
+use phase::HenyeyGreenstein;
+use quaternion::Quaternion;
 use quad::Quad;
 use ray::Ray;
+use sky::Sky;
 use sphere::Sphere;
 use texture::{CheckerTexture, SolidColor};
 use texture_image::TextureImage;
-use transfomation::{RotateY, Translate};
+use transfomation::{AnimatedTransform, Keyframe, RotateY, Transform, Translate};
 use vec3::{Point3, Vec3};
 
 mod vec2;
@@ -35,6 +45,27 @@ mod transfomation;
 mod constant_medium;
 mod onb;
 mod pdf;
+mod thin_film;
+mod coated;
+mod mix_material;
+mod diffuse;
+mod subsurface;
+mod phase;
+mod heterogeneous_medium;
+mod voxel_grid;
+mod blackbody;
+mod fog;
+mod background;
+mod sky;
+mod distribution;
+mod environment_map;
+mod light;
+mod triangle;
+mod aabb;
+mod light_bvh;
+mod ies;
+mod mat4;
+mod quaternion;
 
 const ASPECT_RATIO: f64 = 3.0 / 2.0;
 const IMAGE_WIDTH: i32 = 400;
@@ -71,9 +102,7 @@ fn cornell_smoke() {
     let box2 = Arc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0)));
 
     world.add(Arc::new(ConstantMedium::from_color(box1.clone(), 0.01, Color::new(0.0, 0.0, 0.0))));
-    world.add(Arc::new(ConstantMedium::from_color(box2.clone(), 0.01, Color::new(1.0, 1.0, 1.0))));
-
-    let lights = Arc::new(Quad::new(Point3::new(343.0, 554.0, 332.0), Vec3::new(-130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -105.0), Arc::new(Empty::new())));
+    world.add(Arc::new(ConstantMedium::new(box2.clone(), 0.01, Arc::new(HenyeyGreenstein::from_color(Color::new(1.0, 1.0, 1.0), 0.6)))));
 
 
     let eye = Point3::new(278.0, 278.0, -800.0);
@@ -83,13 +112,380 @@ fn cornell_smoke() {
     let aperture = 0.0;
     let camera = Camera::new(600, 600, 200, MAX_DEPTH, eye, lookat, up, 40.0, 1.0, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
 
-    camera.render(&world, lights);
+    camera.render(&world, Arc::new(world.lights()));
 }
 
-fn cornell_box() {
+// a torus-like ring of overlapping boxes filled with smoke, seen from inside a thin haze
+fn concave_fog() {
+    let mut world = HittableList::new();
+    let ground = Arc::new(Lambertian::from_color(Color::new(0.48, 0.83, 0.53)));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), ground)));
+
+    let light = DiffuseLight::from_color(Color::new(7.0, 7.0, 7.0));
+    world.add(Arc::new(Quad::new(Point3::new(-2.0, 8.0, -2.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 4.0), Arc::new(light))));
+
+    let boundary_material = Arc::new(Empty::new());
+    let mut ring = HittableList::new();
+    for i in 0..12 {
+        let angle = i as f64 * 30.0;
+        let segment = Arc::new(Quad::get_box(Point3::new(-0.9, -0.9, -0.9), Point3::new(0.9, 0.9, 0.9), boundary_material.clone()));
+        let segment = Arc::new(RotateY::new(segment, -angle));
+        let radians = common::degrees_to_radians(angle);
+        ring.add(Arc::new(Translate::new(segment, Vec3::new(3.0 * f64::cos(radians), 1.5, 3.0 * f64::sin(radians)))));
+    }
+    world.add(Arc::new(ConstantMedium::from_color(Arc::new(ring), 0.8, Color::new(0.9, 0.9, 0.9))));
+
+    let haze = Arc::new(Quad::get_box(Point3::new(-20.0, 0.0, -20.0), Point3::new(20.0, 10.0, 20.0), boundary_material));
+    world.add(Arc::new(ConstantMedium::from_color(haze, 0.01, Color::new(1.0, 1.0, 1.0))));
+
+    let eye = Point3::new(0.0, 5.0, -12.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// an ice cube poking out of a ball of water; the ice has priority where the two overlap
+fn ice_in_water() {
+    let mut world = HittableList::new();
+    let checker_texture = CheckerTexture::from_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Arc::new(Quad::new(Point3::new(-10.0, 0.0, -10.0), Vec3::new(20.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 20.0), Arc::new(Lambertian::new(Box::new(checker_texture))))));
+
+    let light = DiffuseLight::from_color(Color::new(10.0, 10.0, 10.0));
+    world.add(Arc::new(Quad::new(Point3::new(-1.5, 6.0, -1.5), Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 3.0), Arc::new(light))));
+
+    let water = Arc::new(Dielectric::nested(1.33, Color::new(0.3, 0.08, 0.02), 1));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.5, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), water, 1.5)));
+
+    let ice = Arc::new(Dielectric::nested(1.31, Color::new(0.0, 0.0, 0.0), 2));
+    let cube = Arc::new(Quad::get_box(Point3::new(-0.6, -0.6, -0.6), Point3::new(0.6, 0.6, 0.6), ice));
+    let cube = Arc::new(RotateY::new(cube, 30.0));
+    world.add(Arc::new(Translate::new(cube, Vec3::new(0.4, 2.7, -0.3))));
+
+    let eye = Point3::new(0.0, 4.0, -9.0);
+    let lookat = Point3::new(0.0, 1.5, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 30.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+fn daylight() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+    world.add(Arc::new(Quad::new(Point3::new(-50.0, 0.0, -50.0), Vec3::new(100.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 100.0), Arc::new(ground))));
+
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(-2.2, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.8, 0.3, 0.2))), 1.0)));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Dielectric::new(1.5)), 1.0)));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(2.2, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Metal::new(Box::new(SolidColor::new(Color::new(0.8, 0.8, 0.8))), 0.1)), 1.0)));
+
+    // late afternoon sun, also sampled directly as a light
+    let sky = Arc::new(Sky::new(Vec3::new(-1.0, 0.35, -0.6), 3.0, Color::new(0.3, 0.3, 0.3)));
+    let mut lights = world.lights();
+    lights.add(sky.clone());
+
+    let eye = Point3::new(0.0, 2.0, 9.0);
+    let lookat = Point3::new(0.0, 1.5, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let mut camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+    camera.set_background(sky);
+
+    camera.render(&world, Arc::new(lights));
+}
+
+fn environment_lit() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+    world.add(Arc::new(Quad::new(Point3::new(-50.0, 0.0, -50.0), Vec3::new(100.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 100.0), Arc::new(ground))));
+
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(-1.1, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.7, 0.7, 0.7))), 1.0)));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(1.1, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Metal::new(Box::new(SolidColor::new(Color::new(0.9, 0.9, 0.9))), 0.05)), 1.0)));
+
+    // any equirectangular image works, though an .hdr or .exr one lights the scene far better
+    let environment = match EnvironmentMap::new("assets/earthmap.jpg", 1.0, 90.0) {
+        Ok(environment) => Arc::new(environment),
+        Err(e) => {
+            eprintln!("Error: {}, the scene has nothing to light it.", e);
+            return;
+        }
+    };
+    let mut lights = world.lights();
+    lights.add(environment.clone());
+
+    let eye = Point3::new(0.0, 2.0, 7.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let mut camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+    camera.set_background(environment);
+
+    camera.render(&world, Arc::new(lights));
+}
+
+fn delta_lights() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.6, 0.6, 0.6));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(-2.2, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.8, 0.3, 0.2))), 1.0)));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.2, 0.6, 0.3))), 1.0)));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(2.2, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.2, 0.3, 0.8))), 1.0)));
+
+    // a dim area light keeps working alongside the delta lights
+    let light = DiffuseLight::from_color(Color::new(2.0, 2.0, 2.0));
+    world.add(Arc::new(Quad::new(Point3::new(-1.0, 6.0, -3.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), Arc::new(light))));
+
+    let mut lights = world.lights();
+    lights.add(Arc::new(PointLight::new(Point3::new(-4.0, 4.0, 3.0), Color::new(20.0, 18.0, 15.0))));
+    lights.add(Arc::new(SpotLight::new(Point3::new(2.2, 5.0, 2.0), Vec3::new(0.0, -5.0, -2.0), Color::new(40.0, 40.0, 50.0), 20.0, 12.0)));
+    lights.add(Arc::new(DirectionalLight::new(Vec3::new(1.0, -1.0, -0.5), Color::new(0.3, 0.25, 0.2))));
+
+    let eye = Point3::new(0.0, 3.0, 9.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.02, 0.02, 0.03));
+
+    camera.render(&world, Arc::new(lights));
+}
+
+// a few hundred small lamps of very different brightness over a floor, picked between with a light bvh
+fn many_lights() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.7, 0.7, 0.7))), 1.0)));
+
+    for i in 0..20 {
+        for j in 0..20 {
+            let center = Point3::new(-9.5 + i as f64, 0.2 + 0.6 * random_double(), -9.5 + j as f64);
+            let strength = 40.0 * random_double() * random_double();
+            let lamp = Arc::new(Sphere::new(Ray::new(center, Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(DiffuseLight::from_color(strength * Color::random_range(0.2, 1.0))), 0.1));
+            world.add(lamp);
+        }
+    }
+
+    // a faint glowing backdrop, too big and dim to be worth sampling
+    let backdrop = DiffuseLight::from_color(Color::new(0.05, 0.05, 0.08));
+    world.add_without_light_sampling(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -12.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 20.0, 0.0), Arc::new(backdrop))));
+
+    let mut lights = world.lights();
+    lights.set_selection(LightSelection::Bvh);
+
+    let eye = Point3::new(0.0, 6.0, 12.0);
+    let lookat = Point3::new(0.0, 0.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(lights));
+}
+
+// area lights that don't emit evenly: a cosine power lobe, a two sided panel and a measured downlight
+fn emission_profiles() {
     let mut world = HittableList::new();
-    let mut lights = HittableList::new();
 
+    let ground = Lambertian::from_color(Color::new(0.6, 0.6, 0.6));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+    let back_wall = Lambertian::from_color(Color::new(0.5, 0.5, 0.5));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -4.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 20.0, 0.0), Arc::new(back_wall))));
+
+    let narrow = DiffuseLight::with_profile(Box::new(SolidColor::new(Color::new(30.0, 28.0, 24.0))), EmissionProfile::CosinePower(20.0), false);
+    world.add(Arc::new(Quad::new(Point3::new(-4.0, 4.0, -0.5), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Arc::new(narrow))));
+
+    let panel = DiffuseLight::with_profile(Box::new(SolidColor::new(Color::new(4.0, 4.0, 6.0))), EmissionProfile::Lambertian, true);
+    world.add(Arc::new(Quad::new(Point3::new(0.0, 0.5, -1.5), Vec3::new(0.0, 0.0, 2.0), Vec3::new(0.0, 2.5, 0.0), Arc::new(panel))));
+
+    // without the photometric file a similarly narrow beam stands in for it
+    let downlight = match IesProfile::new("assets/downlight.ies") {
+        Ok(profile) => EmissionProfile::Ies(Arc::new(profile)),
+        Err(e) => {
+            eprintln!("Warning: {}, using a cosine power beam instead.", e);
+            EmissionProfile::CosinePower(10.0)
+        }
+    };
+    let ies = DiffuseLight::with_profile(Box::new(SolidColor::new(Color::new(25.0, 22.0, 18.0))), downlight, false);
+    world.add(Arc::new(Quad::new(Point3::new(3.5, 4.0, -0.5), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Arc::new(ies))));
+
+    let eye = Point3::new(0.0, 3.0, 10.0);
+    let lookat = Point3::new(0.0, 1.5, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// warm to cool panels given by colour temperature, the same brightness whatever their size
+fn color_temperatures() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.7, 0.7, 0.7));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+    let white = Arc::new(Lambertian::from_color(Color::new(0.8, 0.8, 0.8)));
+
+    // the same output in lumens from a small and a large panel, and a filament lamp rated in watts
+    let panels = [
+        (-3.0, 2700.0, LuminousPower::Lumens(20.0), 1.0),
+        (0.0, 4000.0, LuminousPower::Lumens(20.0), 2.0),
+        (3.0, 6500.0, LuminousPower::Watts(1.0), 1.0)
+    ];
+    for (x, temperature, power, size) in panels {
+        let light = DiffuseLight::from_temperature(temperature, power, size * size);
+        world.add(Arc::new(Quad::new(Point3::new(x - size / 2.0, 3.0, -size / 2.0), Vec3::new(size, 0.0, 0.0), Vec3::new(0.0, 0.0, size), Arc::new(light))));
+        world.add(Arc::new(Sphere::new(Ray::new(Point3::new(x, 0.7, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), white.clone(), 0.7)));
+    }
+
+    let eye = Point3::new(0.0, 2.0, 10.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// a glowing ball streaking past, lit and sampled where it is at each ray's time
+fn moving_light() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.6, 0.6, 0.6));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.0, -1.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::from_color(Color::new(0.7, 0.3, 0.2))), 1.0)));
+
+    let light = DiffuseLight::from_color(Color::new(12.0, 11.0, 9.0));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(-3.0, 2.2, 1.0), Vec3::new(6.0, 0.0, 0.0), 0.0), Arc::new(light), 0.4)));
+
+    let eye = Point3::new(0.0, 3.0, 10.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// one box shared between instances turned about tilted axes, squashed and aimed, lit by a
+// stretched glowing sphere
+fn transformed_instances() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.6, 0.6, 0.6));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+
+    let white = Arc::new(Lambertian::from_color(Color::new(0.75, 0.75, 0.75)));
+    let unit_box: Arc<dyn Hittable> = Arc::new(Quad::get_box(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5), white));
+
+    for i in 0..8 {
+        let angle = 45.0 * i as f64;
+        let placement = Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), angle)
+            * Mat4::translation(Vec3::new(3.0, 0.6, 0.0))
+            * Mat4::rotation(Vec3::new(1.0, 1.0, 0.0), 20.0 * i as f64)
+            * Mat4::scaling(Vec3::new(0.6, 1.0 + 0.1 * i as f64, 0.4));
+        world.add(Arc::new(Transform::new(unit_box.clone(), placement)));
+    }
+
+    // a long thin slab aimed at the middle of the ring from above
+    let slab = Transform::scale(unit_box.clone(), Vec3::new(0.3, 0.3, 2.0));
+    world.add(Arc::new(slab.then(Mat4::look_at(Point3::new(-1.0, 3.5, -1.0), Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)))));
+
+    let light = DiffuseLight::from_color(Color::new(15.0, 14.0, 12.0));
+    let lamp = Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(light), 1.0));
+    let lamp = Transform::scale(lamp, Vec3::new(1.5, 0.2, 0.5)).then(Mat4::translation(Vec3::new(0.0, 4.0, 1.0)));
+    world.add(Arc::new(lamp));
+
+    let eye = Point3::new(0.0, 6.0, 10.0);
+    let lookat = Point3::new(0.0, 0.5, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.05, 0.05, 0.07));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// boxes blurred by sliding, tumbling and swelling while the shutter is open, under a spinning lamp
+fn animated_transforms() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.6, 0.6, 0.6));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+
+    let red = Arc::new(Lambertian::from_color(Color::new(0.7, 0.2, 0.15)));
+    let unit_box: Arc<dyn Hittable> = Arc::new(Quad::get_box(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5), red));
+    let upright = Quaternion::identity();
+    let unit = Vec3::new(1.0, 1.0, 1.0);
+
+    let sliding = AnimatedTransform::between(unit_box.clone(), Keyframe::new(0.0, Vec3::new(-3.5, 0.5, 0.0), upright, unit), Keyframe::new(1.0, Vec3::new(-2.0, 0.5, 0.0), upright, unit));
+    world.add(Arc::new(sliding));
+
+    let tumbling = AnimatedTransform::new(unit_box.clone(), vec![
+        Keyframe::new(0.0, Vec3::new(0.0, 1.2, 0.0), upright, unit),
+        Keyframe::new(0.5, Vec3::new(0.0, 1.0, 0.0), Quaternion::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 40.0), unit),
+        Keyframe::new(1.0, Vec3::new(0.0, 1.2, 0.0), Quaternion::from_axis_angle(Vec3::new(1.0, 1.0, 0.0), 80.0), unit)
+    ]);
+    world.add(Arc::new(tumbling));
+
+    let swelling = AnimatedTransform::between(unit_box.clone(), Keyframe::new(0.0, Vec3::new(3.0, 0.4, 0.0), upright, Vec3::new(0.8, 0.8, 0.8)), Keyframe::new(1.0, Vec3::new(3.0, 0.75, 0.0), upright, Vec3::new(1.5, 1.5, 1.5)));
+    world.add(Arc::new(swelling));
+
+    let light = DiffuseLight::from_color(Color::new(20.0, 19.0, 18.0));
+    let lamp: Arc<dyn Hittable> = Arc::new(Quad::new(Point3::new(-1.5, 0.0, -0.3), Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.6), Arc::new(light)));
+    let spinning = AnimatedTransform::between(lamp, Keyframe::new(0.0, Vec3::new(0.0, 4.0, 0.0), upright, unit), Keyframe::new(1.0, Vec3::new(0.0, 4.0, 0.0), Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 90.0), unit));
+    world.add(Arc::new(spinning));
+
+    let eye = Point3::new(0.0, 3.0, 10.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.05, 0.05, 0.07));
+
+    camera.render(&world, Arc::new(world.lights()));
+}
+
+// a noisy cloud under a spot light, its shadow on the ground softened by ratio tracking
+fn heterogeneous_smoke() {
+    let mut world = HittableList::new();
+
+    let ground = Lambertian::from_color(Color::new(0.7, 0.7, 0.7));
+    world.add(Arc::new(Quad::new(Point3::new(-20.0, 0.0, -20.0), Vec3::new(40.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 40.0), Arc::new(ground))));
+
+    let boundary = Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 1.5, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Empty::new()), 1.3));
+    world.add(Arc::new(HeterogeneousMedium::from_color(boundary, Box::new(NoiseDensity::new(2.0, 3.0)), Color::new(0.9, 0.9, 0.9))));
+
+    let mut lights = world.lights();
+    lights.add(Arc::new(SpotLight::new(Point3::new(1.0, 6.0, 1.0), Vec3::new(-1.0, -6.0, -1.0), Color::new(60.0, 58.0, 52.0), 40.0, 30.0)));
+
+    let eye = Point3::new(0.0, 3.0, 9.0);
+    let lookat = Point3::new(0.0, 1.0, 0.0);
+    let up = Point3::new(0.0, 1.0, 0.0);
+    let dist_to_focus = (eye - lookat).length();
+    let aperture = 0.0;
+    let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.02, 0.02, 0.03));
+
+    camera.render(&world, Arc::new(lights));
+}
+
+fn cornell_box() {
+    let mut world = HittableList::new();
     let red = Lambertian::from_color(Color::new(0.65, 0.05, 0.05));
     let white = Arc::new(Lambertian::from_color(Color::new(0.73, 0.73, 0.73)));
     let aluminum = Arc::new(Metal::from_color(Color::new(0.8, 0.85, 0.88), 0.0));
@@ -111,7 +507,6 @@ fn cornell_box() {
     let glass = Arc::new(Dielectric::new(1.5));
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(190.0, 90.0, 190.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), glass, 90.0)));
 
-    lights.add(Arc::new(Quad::new(Point3::new(343.0, 554.0, 332.0), Vec3::new(-130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -105.0), Arc::new(Empty::new()))));
 
     let eye = Point3::new(278.0, 278.0, -800.0);
     let lookat = Point3::new(278.0, 278.0, 0.0);
@@ -120,7 +515,7 @@ fn cornell_box() {
     let aperture = 0.0;
     let camera = Camera::new(600, 600, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 40.0, 1.0, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
 
-    camera.render(&world, Arc::new(lights));
+    camera.render(&world, Arc::new(world.lights()));
 }
 
 fn simple_light() {
@@ -132,13 +527,11 @@ fn simple_light() {
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, -1000.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::new(Box::new(perlin_texture_one))), 1000.0)));
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 2.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::new(Box::new(perlin_texture_two))), 2.0)));
     
-    let mut lights = HittableList::new();
-
     let diff_light_one = DiffuseLight::from_color(Color::new(4.0, 4.0, 4.0));
     let diff_light_two = DiffuseLight::from_color(Color::new(4.0, 4.0, 4.0));
 
-    lights.add(Arc::new(Quad::new(Point3::new(3.0, 1.0, -2.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Arc::new(diff_light_one))));
-    lights.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 7.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(diff_light_two), 2.0)));
+    world.add(Arc::new(Quad::new(Point3::new(3.0, 1.0, -2.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Arc::new(diff_light_one))));
+    world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 7.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(diff_light_two), 2.0)));
 
 
     let eye = Point3::new(26.0, 3.0, 6.0);
@@ -148,7 +541,7 @@ fn simple_light() {
     let aperture = 0.0;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 20.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.0, 0.0, 0.0));
 
-    camera.render(&world, Arc::new(lights));
+    camera.render(&world, Arc::new(world.lights()));
 }
 
 fn quads() {
@@ -166,7 +559,6 @@ fn quads() {
     world.add(Arc::new(Quad::new(Point3::new(-2.0, 3.0, 1.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 4.0), Arc::new(upper_orange))));
     world.add(Arc::new(Quad::new(Point3::new(-2.0, -3.0, 5.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -4.0), Arc::new(lower_teal))));
 
-    let lights = Arc::new(HittableList::new());
 
     let eye = Point3::new(0.0, 0.0, 9.0);
     let lookat = Point3::new(0.0, 0.0, 0.0);
@@ -175,7 +567,7 @@ fn quads() {
     let aperture = 0.0;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 80.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.7, 0.8, 1.0));
 
-    camera.render(&world, lights);
+    camera.render(&world, Arc::new(world.lights()));
 }
 
 fn perlin_spheres() {
@@ -186,7 +578,6 @@ fn perlin_spheres() {
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, -1000.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::new(Box::new(perlin_texture_one))), 1000.0)));
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 2.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::new(Box::new(perlin_texture_two))), 2.0)));
 
-    let lights = Arc::new(HittableList::new());
 
     let eye = Point3::new(13.0, 2.0, 3.0);
     let lookat = Point3::new(0.0, 0.0, 0.0);
@@ -195,12 +586,11 @@ fn perlin_spheres() {
     let aperture = 0.0;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 20.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.7, 0.8, 1.0));
 
-    camera.render(&world, lights);
+    camera.render(&world, Arc::new(world.lights()));
 }
 
 fn earth() {
     let mut world = HittableList::new();
-    let lights = HittableList::new();
 
     let earth_texture = TextureImage::new("assets/earthmap.jpg");
     let earth_mat = Lambertian::new(Box::new(earth_texture));
@@ -214,7 +604,7 @@ fn earth() {
     let aperture = 0.0;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 20.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.7, 0.8, 1.0));
 
-    camera.render(&world, Arc::new(lights));
+    camera.render(&world, Arc::new(world.lights()));
 }
 
 fn checkered_spheres() {
@@ -225,7 +615,6 @@ fn checkered_spheres() {
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, -10.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Metal::new(Box::new(checker_texture_one), 0.1)), 10.0)));
     world.add(Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 10.0, 0.0) , Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Lambertian::new(Box::new(checker_texture_two))), 10.0)));
 
-    let lights = Arc::new(HittableList::new());
 
     let eye = Point3::new(13.0, 2.0, 3.0);
     let lookat = Point3::new(0.0, 0.0, 0.0);
@@ -234,7 +623,7 @@ fn checkered_spheres() {
     let aperture = 0.0;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 20.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.7, 0.8, 1.0));
 
-    camera.render(&world, lights);
+    camera.render(&world, Arc::new(world.lights()));
 
 }
 
@@ -296,7 +685,6 @@ fn random_scene() {
         1.0,
     )));
 
-    let lights = Arc::new(HittableList::new());
  
     let eye = Point3::new(13.0, 2.0, 3.0);
     let lookat = Point3::new(0.0, 0.0, 0.0);
@@ -305,5 +693,5 @@ fn random_scene() {
     let aperture = 0.1;
     let camera = Camera::new(IMAGE_WIDTH, IMAGE_HEIGHT, SAMPLES_PER_PIXEL, MAX_DEPTH, eye, lookat, up, 20.0, ASPECT_RATIO, aperture, dist_to_focus, Color::new(0.7, 0.8, 1.0));
 
-    camera.render(&world, lights);
+    camera.render(&world, Arc::new(world.lights()));
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+use std::ops::Mul;
+
+use crate::{common::degrees_to_radians, vec3::{cross, unit_vector, Point3, Vec3}};
+
+// A 4x4 matrix acting on column vectors, for affine transforms of points, directions and normals
+#[derive(Clone, Copy)]
+pub struct Mat4 {
+    m: [[f64; 4]; 4]
+}
+
+impl Mat4 {
+    pub fn new(m: [[f64; 4]; 4]) -> Mat4 {
+        Mat4 { m }
+    }
+
+    pub fn identity() -> Mat4 {
+        Mat4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        Mat4::new([
+            [1.0, 0.0, 0.0, offset.x()],
+            [0.0, 1.0, 0.0, offset.y()],
+            [0.0, 0.0, 1.0, offset.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factors: Vec3) -> Mat4 {
+        Mat4::new([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // counterclockwise about axis when looking down it towards the origin
+    pub fn rotation(axis: Vec3, angle: f64) -> Mat4 {
+        let a = unit_vector(axis);
+        let radians = degrees_to_radians(angle);
+        let (sin_theta, cos_theta) = (f64::sin(radians), f64::cos(radians));
+        let k = 1.0 - cos_theta;
+
+        Mat4::new([
+            [a.x() * a.x() * k + cos_theta, a.x() * a.y() * k - a.z() * sin_theta, a.x() * a.z() * k + a.y() * sin_theta, 0.0],
+            [a.y() * a.x() * k + a.z() * sin_theta, a.y() * a.y() * k + cos_theta, a.y() * a.z() * k - a.x() * sin_theta, 0.0],
+            [a.z() * a.x() * k - a.y() * sin_theta, a.z() * a.y() * k + a.x() * sin_theta, a.z() * a.z() * k + cos_theta, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // places the origin at eye with +z pointing at target and +y as close to up as it can be
+    pub fn look_at(eye: Point3, target: Point3, up: Vec3) -> Mat4 {
+        let w = unit_vector(target - eye);
+        let u = unit_vector(cross(up, w));
+        let v = cross(w, u);
+
+        Mat4::new([
+            [u.x(), v.x(), w.x(), eye.x()],
+            [u.y(), v.y(), w.y(), eye.y()],
+            [u.z(), v.z(), w.z(), eye.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+
+        Mat4::new(m)
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None for singular matrices
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| f64::abs(a[i][col]).total_cmp(&f64::abs(a[j][col])))?;
+            if f64::abs(a[pivot][col]) < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Mat4::new(inv))
+    }
+
+    // determinant of the linear part, how much the transform scales volumes
+    pub fn determinant3(&self) -> f64 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        self.transform_vector(p) + Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    // directions ignore the translation
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+// a * b applies b first, then a
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, b: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * b.m[k][j]).sum();
+            }
+        }
+
+        Mat4::new(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &Mat4, b: &Mat4) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(f64::abs(a.m[i][j] - b.m[i][j]) < 1e-9, "{:?} against {:?}", a.m, b.m);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0)) * Mat4::rotation(Vec3::new(1.0, 2.0, 0.5), 37.0) * Mat4::scaling(Vec3::new(2.0, 0.5, 3.0));
+        let inverse = m.inverse().unwrap();
+
+        assert_close(&(m * inverse), &Mat4::identity());
+        assert_close(&(inverse * m), &Mat4::identity());
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        assert!(Mat4::scaling(Vec3::new(1.0, 0.0, 1.0)).inverse().is_none());
+        assert!(Mat4::new([[1.0, 2.0, 3.0, 0.0], [2.0, 4.0, 6.0, 0.0], [0.0, 0.0, 1.0, 0.0], [0.0, 0.0, 0.0, 1.0]]).inverse().is_none());
+    }
+
+    #[test]
+    fn products_apply_the_right_hand_matrix_first() {
+        let scale = Mat4::scaling(Vec3::new(2.0, 2.0, 2.0));
+        let translate = Mat4::translation(Vec3::new(1.0, 0.0, 0.0));
+        let p = Point3::new(1.0, 1.0, 1.0);
+
+        let scaled_then_moved = (translate * scale).transform_point(p);
+        let moved_then_scaled = (scale * translate).transform_point(p);
+        assert!((scaled_then_moved - Point3::new(3.0, 2.0, 2.0)).length() < 1e-12);
+        assert!((moved_then_scaled - Point3::new(4.0, 2.0, 2.0)).length() < 1e-12);
+    }
+}
@@ -1,21 +1,49 @@
 use std::sync::Arc;
 
-use crate::{color::Color, common::random_double, hittable::HitRecord, onb::Onb, pdf::{CosinePdf, Pdf, SpherePdf}, ray::Ray, texture::{SolidColor, Texture}, vec3::{self, dot, random_unit_vector, unit_vector, Point3, Vec3}};
+use crate::{blackbody::{BlackbodyTexture, LuminousPower}, color::Color, common::random_double, hittable::HitRecord, ies::IesProfile, onb::Onb, pdf::{CosinePdf, Pdf, SpherePdf}, ray::Ray, texture::{SolidColor, Texture}, thin_film::ThinFilm, vec3::{self, dot, random_unit_vector, unit_vector, Point3, Vec3}};
 
 pub struct ScatterRecord {
     pub attenuation: Color,
     pub pdf: Option<Arc<dyn Pdf>>,
     pub scattered: Ray,
+    // the material a mix picked to scatter with, whose scatter_pdf goes with this record
+    pub lobe: Option<Arc<dyn Material>>
+}
+
+// the medium enclosed by a nestable material's surfaces; where several overlap the highest priority one wins
+#[derive(Clone, Copy)]
+pub struct Interior {
+    pub ior: f64,
+    pub priority: i32,
+    pub absorption: Color,
+    pub id: usize
 }
 
 pub trait Material: Send + Sync {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord>;
-    fn emitted(&self, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+    fn emitted(&self, r_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
+    // surfaces with emissive materials are picked up as lights to sample
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    fn emission_spread(&self) -> EmissionSpread {
+        EmissionSpread {
+            cos_theta_e: 0.0,
+            two_sided: false
+        }
+    }
     fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
         0.0
     }
+    fn interior(&self) -> Option<Interior> {
+        None
+    }
+    // scatter off an interface whose two sides have the given indices of refraction
+    fn scatter_between(&self, r_in: &Ray, rec: &HitRecord, _incident_ior: f64, _transmitted_ior: f64) -> Option<ScatterRecord> {
+        self.scatter(r_in, rec)
+    }
 }
 
 pub struct Empty {}
@@ -61,7 +89,8 @@ impl Material for Lambertian {
         Some(ScatterRecord {
             attenuation: self.albedo.get_color(rec.u, rec.v, &rec.p),
             scattered: Ray::new(rec.p, scatter_direction, r_in.time()),
-            pdf: Some(Arc::new(CosinePdf::new(rec.normal)))
+            pdf: Some(Arc::new(CosinePdf::new(rec.normal))),
+            lobe: None
         })
     }
 
@@ -77,14 +106,16 @@ impl Material for Lambertian {
 
 pub struct Metal {
     albedo: Box<dyn Texture>,
-    fuzz: f64
+    fuzz: f64,
+    thin_film: Option<ThinFilm>
 }
 
 impl Metal {
     pub fn new(albedo: Box<dyn Texture>, f: f64) -> Metal {
         Metal { 
             albedo,
-            fuzz: if f < 1.0 {f} else {1.0}
+            fuzz: if f < 1.0 {f} else {1.0},
+            thin_film: None
         }
     }
 
@@ -93,7 +124,16 @@ impl Metal {
 
         Metal {
             albedo: Box::new(albedo),
-            fuzz: if f < 1.0 {f} else {1.0}
+            fuzz: if f < 1.0 {f} else {1.0},
+            thin_film: None
+        }
+    }
+
+    pub fn with_thin_film(albedo: Box<dyn Texture>, f: f64, thin_film: ThinFilm) -> Metal {
+        Metal {
+            albedo,
+            fuzz: if f < 1.0 {f} else {1.0},
+            thin_film: Some(thin_film)
         }
     }
 }
@@ -104,10 +144,20 @@ impl Material for Metal {
 
         let scattered = Ray::new(rec.p, reflected + self.fuzz * vec3::random_in_unit_sphere(), r_in.time());
         if  vec3::dot(scattered.direction(), rec.normal) > 0.0 {
+            let albedo = self.albedo.get_color(rec.u, rec.v, &rec.p);
+            let attenuation = match &self.thin_film {
+                Some(thin_film) => {
+                    let cos_theta = f64::min(vec3::dot(-vec3::unit_vector(r_in.direction()), rec.normal), 1.0);
+                    thin_film.conductor_reflectance(cos_theta, 1.0, albedo, rec)
+                },
+                None => albedo
+            };
+
             Some(ScatterRecord {
-                attenuation: self.albedo.get_color(rec.u, rec.v, &rec.p),
+                attenuation,
                 scattered,
-                pdf: None
+                pdf: None,
+                lobe: None
             })
         } else {
             None
@@ -115,18 +165,76 @@ impl Material for Metal {
     }
 }
 
+// the least transmittance from_transmittance takes, keeping absorption finite
+const MIN_TRANSMITTANCE: f64 = 1e-6;
+
 pub struct Dielectric {
-    ior: f64
+    ior: f64,
+    absorption: Color,
+    thin_film: Option<ThinFilm>,
+    priority: i32
 }
 
 impl Dielectric {
     pub fn new(ior: f64) -> Dielectric {
         Dielectric {
-            ior
+            ior,
+            absorption: Color::new(0.0, 0.0, 0.0),
+            thin_film: None,
+            priority: 0
+        }
+    }
+
+    pub fn with_absorption(ior: f64, absorption: Color) -> Dielectric {
+        Dielectric {
+            ior,
+            absorption,
+            thin_film: None,
+            priority: 0
+        }
+    }
+
+    // the color white light takes on after travelling `distance` through the medium; channels
+    // at or below MIN_TRANSMITTANCE are treated as MIN_TRANSMITTANCE, so fully opaque channels
+    // get a large but finite absorption
+    pub fn from_transmittance(ior: f64, transmittance: Color, distance: f64) -> Dielectric {
+        assert!(distance > 0.0, "transmittance should be given over a positive distance");
+
+        let absorption_of = |t: f64| -f64::ln(f64::max(t, MIN_TRANSMITTANCE)) / distance;
+        let absorption = Color::new(
+            absorption_of(transmittance.x()),
+            absorption_of(transmittance.y()),
+            absorption_of(transmittance.z()),
+        );
+
+        Dielectric {
+            ior,
+            absorption,
+            thin_film: None,
+            priority: 0
         }
     }
 
-    fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
+    pub fn with_thin_film(ior: f64, thin_film: ThinFilm) -> Dielectric {
+        Dielectric {
+            ior,
+            absorption: Color::new(0.0, 0.0, 0.0),
+            thin_film: Some(thin_film),
+            priority: 0
+        }
+    }
+
+    // for dielectrics inside one another, e.g. water (lower priority) in a glass (higher priority)
+    pub fn nested(ior: f64, absorption: Color, priority: i32) -> Dielectric {
+        Dielectric {
+            ior,
+            absorption,
+            thin_film: None,
+            priority
+        }
+    }
+
+    pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         // Schlcik's approximation for reflectance
         let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
         r0 = r0 * r0;
@@ -136,34 +244,98 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
-        let refraction_ratio = if rec.front_face {1.0 / self.ior} else {self.ior};
+        let (incident_ior, transmitted_ior) = if rec.front_face {(1.0, self.ior)} else {(self.ior, 1.0)};
+        let mut scatter_rec = self.scatter_between(r_in, rec, incident_ior, transmitted_ior)?;
+
+        // Beer-Lambert: a back face hit means r_in travelled rec.t inside the medium
+        if !rec.front_face {
+            let distance = rec.t * r_in.direction().length();
+            scatter_rec.attenuation *= Color::new(
+                f64::exp(-self.absorption.x() * distance),
+                f64::exp(-self.absorption.y() * distance),
+                f64::exp(-self.absorption.z() * distance),
+            );
+        }
+
+        Some(scatter_rec)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            ior: self.ior,
+            priority: self.priority,
+            absorption: self.absorption,
+            id: self as *const Dielectric as usize
+        })
+    }
+
+    fn scatter_between(&self, r_in: &Ray, rec: &HitRecord, incident_ior: f64, transmitted_ior: f64) -> Option<ScatterRecord> {
+        let refraction_ratio = incident_ior / transmitted_ior;
         let unit_direction = vec3::unit_vector(r_in.direction());
         let cos_theta = f64::min(vec3::dot(-unit_direction, rec.normal), 1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random_double() { 
-             vec3::reflect(unit_direction, rec.normal)
-        } else {
-            vec3::refract(unit_direction, rec.normal, refraction_ratio)
+        let (direction, attenuation) = match &self.thin_film {
+            Some(thin_film) if !cannot_refract => {
+                // the film reflects each channel differently, so pick a branch on the average and reweight
+                let reflectance = thin_film.reflectance(cos_theta, incident_ior, transmitted_ior, rec);
+                let reflect_probability = (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0;
+
+                if reflect_probability > random_double() {
+                    (vec3::reflect(unit_direction, rec.normal), reflectance / reflect_probability)
+                } else {
+                    let transmittance = Color::new(1.0, 1.0, 1.0) - reflectance;
+                    (vec3::refract(unit_direction, rec.normal, refraction_ratio), transmittance / (1.0 - reflect_probability))
+                }
+            },
+            _ => {
+                let direction = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random_double() { 
+                    vec3::reflect(unit_direction, rec.normal)
+                } else {
+                    vec3::refract(unit_direction, rec.normal, refraction_ratio)
+                };
+                (direction, Color::new(1.0, 1.0, 1.0))
+            }
         };
 
         Some(ScatterRecord {
-            attenuation: Color::new(1.0, 1.0, 1.0),
+            attenuation,
             scattered: Ray::new(rec.p, direction, r_in.time()),
-            pdf: None
+            pdf: None,
+            lobe: None
         })
     }
 }
 
+// how emitted radiance falls off away from the surface normal
+pub enum EmissionProfile {
+    Lambertian,
+    // radiance scaled by cos^exponent, narrowing into a spotlight as the exponent grows
+    CosinePower(f64),
+    // relative intensities from a photometric file, with the luminaire's axis along the normal
+    Ies(Arc<IesProfile>)
+}
+
+// the directions around its normal a surface emits into: up to acos(cos_theta_e) away, on one or both sides
+#[derive(Clone, Copy)]
+pub struct EmissionSpread {
+    pub cos_theta_e: f64,
+    pub two_sided: bool
+}
+
 pub struct DiffuseLight {
-    albedo: Box<dyn Texture>
+    albedo: Box<dyn Texture>,
+    profile: EmissionProfile,
+    two_sided: bool
 }
 
 impl DiffuseLight {
     pub fn new(albedo: Box<dyn Texture>) -> Self {
         DiffuseLight {
-            albedo
+            albedo,
+            profile: EmissionProfile::Lambertian,
+            two_sided: false
         }
     }
 
@@ -171,7 +343,25 @@ impl DiffuseLight {
         let albedo = SolidColor::new(albedo_color);
 
         DiffuseLight {
-            albedo: Box::new(albedo)
+            albedo: Box::new(albedo),
+            profile: EmissionProfile::Lambertian,
+            two_sided: false
+        }
+    }
+
+    // a blackbody at a colour temperature in kelvin, bright enough for a one sided emitter of
+    // the given area to give out the power
+    pub fn from_temperature(temperature: f64, power: LuminousPower, area: f64) -> Self {
+        DiffuseLight::new(Box::new(BlackbodyTexture::from_power(temperature, power, area)))
+    }
+
+    // the texture gives the radiance in the brightest direction; two sided lights emit the
+    // same profile mirrored out of their back faces
+    pub fn with_profile(albedo: Box<dyn Texture>, profile: EmissionProfile, two_sided: bool) -> Self {
+        DiffuseLight {
+            albedo,
+            profile,
+            two_sided
         }
     }
 }
@@ -181,11 +371,52 @@ impl Material for DiffuseLight {
         None
     }
 
-    fn emitted(&self, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
-        if hit_rec.front_face {
-            self.albedo.get_color(u, v, p)
-        } else {
-            Color::new(0.0, 0.0, 0.0)
+    fn emitted(&self, r_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        let facing = hit_rec.front_face || self.two_sided;
+        let falloff = match &self.profile {
+            EmissionProfile::Lambertian => if facing {1.0} else {0.0},
+            EmissionProfile::CosinePower(exponent) => {
+                let cos_theta = dot(-unit_vector(r_in.direction()), hit_rec.normal);
+                if facing && cos_theta > 0.0 {f64::powf(cos_theta, *exponent)} else {0.0}
+            },
+            EmissionProfile::Ies(profile) => {
+                // angles are measured from the front normal, so the back face sees the upper half of the data
+                let front_normal = if hit_rec.front_face {hit_rec.normal} else {-hit_rec.normal};
+                let outgoing = -unit_vector(r_in.direction());
+                let mut cos_theta = dot(outgoing, front_normal);
+                if self.two_sided {
+                    cos_theta = f64::abs(cos_theta);
+                }
+                let uvw = Onb::new(&front_normal);
+                let phi = f64::atan2(dot(outgoing, uvw.v()), dot(outgoing, uvw.u()));
+                profile.value(cos_theta, phi)
+            }
+        };
+
+        if falloff <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        falloff * self.albedo.get_color(u, v, p)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+
+    fn emission_spread(&self) -> EmissionSpread {
+        match &self.profile {
+            EmissionProfile::Ies(profile) => {
+                let max_angle = profile.max_angle();
+                EmissionSpread {
+                    cos_theta_e: f64::cos(f64::min(max_angle, 90.0).to_radians()),
+                    two_sided: self.two_sided || max_angle > 90.0
+                }
+            },
+            _ => EmissionSpread {
+                cos_theta_e: 0.0,
+                two_sided: self.two_sided
+            }
         }
     }
 }
@@ -219,11 +450,62 @@ impl Material for Isotropic {
         Some(ScatterRecord {
             attenuation,
             scattered,
-            pdf: Some(Arc::new(SpherePdf::new()))
+            pdf: Some(Arc::new(SpherePdf::new())),
+            lobe: None
         })
     }
 
     fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
         1.0 / (4.0 * std::f64::consts::PI)
     }
-}
\ No newline at end of file
+}
+
+// an isotropic phase function for media that also glow; every collision is partly an
+// absorption, which is where the emission comes from
+pub struct EmissiveIsotropic {
+    albedo: Box<dyn Texture>,
+    emission: Box<dyn Texture>
+}
+
+impl EmissiveIsotropic {
+    pub fn new(albedo: Box<dyn Texture>, emission: Box<dyn Texture>) -> Self {
+        EmissiveIsotropic {
+            albedo,
+            emission
+        }
+    }
+
+    pub fn from_color(albedo_color: Color, emission: Box<dyn Texture>) -> Self {
+        let albedo = SolidColor::new(albedo_color);
+
+        EmissiveIsotropic {
+            albedo: Box::new(albedo),
+            emission
+        }
+    }
+}
+
+impl Material for EmissiveIsotropic {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let attenuation = self.albedo.get_color(rec.u, rec.v, &rec.p);
+        if attenuation.near_zero() {
+            return None;
+        }
+
+        Some(ScatterRecord {
+            attenuation,
+            scattered: Ray::new(rec.p, random_unit_vector(), r_in.time()),
+            pdf: Some(Arc::new(SpherePdf::new())),
+            lobe: None
+        })
+    }
+
+    fn emitted(&self, _r_in: &Ray, _hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        let absorbed = Color::new(1.0, 1.0, 1.0) - self.albedo.get_color(u, v, p);
+        absorbed * self.emission.get_color(u, v, p)
+    }
+
+    fn scatter_pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * std::f64::consts::PI)
+    }
+}
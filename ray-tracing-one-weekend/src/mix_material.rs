@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crate::{color::Color, common::random_double, hittable::HitRecord, material::{EmissionSpread, Material, ScatterRecord}, ray::Ray, texture::Texture, vec3::Point3};
+
+pub struct MixMaterial {
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    mask: Box<dyn Texture>
+}
+
+impl MixMaterial {
+    // the red channel of the mask is the weight of the second material
+    pub fn new(first: Arc<dyn Material>, second: Arc<dyn Material>, mask: Box<dyn Texture>) -> MixMaterial {
+        MixMaterial {
+            first,
+            second,
+            mask
+        }
+    }
+
+    fn weight(&self, u: f64, v: f64, p: &Point3) -> f64 {
+        f64::clamp(self.mask.get_color(u, v, p).x(), 0.0, 1.0)
+    }
+}
+
+impl Material for MixMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        // each scatter picks one of the materials, in proportion to the mask, and that one alone
+        // shades it; a nested mix has already named the lobe it picked in turn
+        let lobe = if random_double() < self.weight(rec.u, rec.v, &rec.p) {&self.second} else {&self.first};
+        let mut scatter_rec = lobe.scatter(r_in, rec)?;
+        scatter_rec.lobe.get_or_insert_with(|| lobe.clone());
+
+        Some(scatter_rec)
+    }
+
+    fn emitted(&self, r_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        let weight = self.weight(u, v, p);
+        (1.0 - weight) * self.first.emitted(r_in, hit_rec, u, v, p) + weight * self.second.emitted(r_in, hit_rec, u, v, p)
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.first.is_emissive() || self.second.is_emissive()
+    }
+
+    fn emission_spread(&self) -> EmissionSpread {
+        let (first, second) = (self.first.emission_spread(), self.second.emission_spread());
+        EmissionSpread {
+            cos_theta_e: f64::min(first.cos_theta_e, second.cos_theta_e),
+            two_sided: first.two_sided || second.two_sided
+        }
+    }
+
+    // the mixture as a whole, for callers without a scatter record naming the lobe
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        let weight = self.weight(rec.u, rec.v, &rec.p);
+        (1.0 - weight) * self.first.scatter_pdf(r_in, rec, scattered) + weight * self.second.scatter_pdf(r_in, rec, scattered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::{Isotropic, Lambertian}, texture::SolidColor, vec3::Vec3};
+
+    // what the camera weights light arriving along scattered by, before dividing by its pdf
+    fn shaded(mat: &Arc<dyn Material>, scattered: &Ray) -> Color {
+        let r_in = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = HitRecord {
+            p: Point3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            mat: mat.clone(),
+            t: 1.0,
+            u: 0.5,
+            v: 0.5,
+            front_face: true
+        };
+
+        let scatter_rec = mat.scatter(&r_in, &rec).unwrap();
+        let lobe = scatter_rec.lobe.clone().unwrap_or_else(|| mat.clone());
+        scatter_rec.attenuation * lobe.scatter_pdf(&r_in, &rec, scattered)
+    }
+
+    fn mix(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: f64) -> Arc<dyn Material> {
+        Arc::new(MixMaterial::new(first, second, Box::new(SolidColor::new(Color::new(weight, weight, weight)))))
+    }
+
+    #[test]
+    fn mixing_a_material_with_itself_changes_nothing() {
+        let lambertian: Arc<dyn Material> = Arc::new(Lambertian::from_color(Color::new(0.8, 0.4, 0.2)));
+        let mixed = mix(lambertian.clone(), lambertian.clone(), 0.3);
+        let scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.3, 1.0, 0.2), 0.0);
+
+        let expected = shaded(&lambertian, &scattered);
+        for _ in 0..100 {
+            let value = shaded(&mixed, &scattered);
+            assert!((value - expected).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn lobes_are_weighted_by_the_mask_once() {
+        let lambertian: Arc<dyn Material> = Arc::new(Lambertian::from_color(Color::new(0.8, 0.8, 0.8)));
+        let isotropic: Arc<dyn Material> = Arc::new(Isotropic::from_color(Color::new(0.5, 0.5, 0.5)));
+        let weight = 0.3;
+        let mixed = mix(lambertian.clone(), isotropic.clone(), weight);
+        let scattered = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+
+        let runs = 20000;
+        let mean = (0..runs).map(|_| shaded(&mixed, &scattered).x()).sum::<f64>() / runs as f64;
+        let expected = (1.0 - weight) * shaded(&lambertian, &scattered).x() + weight * shaded(&isotropic, &scattered).x();
+        assert!(f64::abs(mean - expected) < 0.02 * expected, "{} against {}", mean, expected);
+    }
+}
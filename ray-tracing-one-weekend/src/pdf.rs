@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{common::random_double, hittable::Hittable, onb::Onb, vec3::{dot, random_unit_vector, unit_vector, Point3, Vec3}};
+use crate::{common::random_double, light::LightList, onb::Onb, vec3::{dot, random_unit_vector, unit_vector, Point3, Vec3}};
 
 pub trait Pdf {
     fn value(&self, direction: Vec3) -> f64;
@@ -48,24 +48,24 @@ impl Pdf for CosinePdf {
     }
 }
 
-pub struct HittablePdf {
+pub struct LightPdf {
     origin: Point3,
-    objects: Arc<dyn Hittable>
+    time: f64,
+    lights: Arc<LightList>
 }
 
-impl HittablePdf {
-    pub fn new(origin: Point3, objects: Arc<dyn Hittable>) -> HittablePdf {
-        HittablePdf { origin, objects }
+impl LightPdf {
+    pub fn new(origin: Point3, time: f64, lights: Arc<LightList>) -> LightPdf {
+        LightPdf { origin, time, lights }
     }
-}
 
-impl Pdf for HittablePdf {
-    fn value(&self, direction: Vec3) -> f64 {
-        self.objects.pdf_value(self.origin, direction)
+    pub fn value(&self, direction: Vec3) -> f64 {
+        self.lights.area_light_pdf(self.origin, direction, self.time)
     }
 
-    fn generate(&self) -> Vec3 {
-        self.objects.random(self.origin)
+    // only directions the lights themselves give, None when none of them could be sampled
+    pub fn generate(&self) -> Option<Vec3> {
+        self.lights.sample_area_light(self.origin, self.time).map(|light_sample| light_sample.direction)
     }
 }
 
@@ -92,4 +92,111 @@ impl Pdf for MixturePdf {
             self.q.generate()
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * std::f64::consts::PI * denom * f64::sqrt(denom))
+}
+
+pub fn rayleigh(cos_theta: f64) -> f64 {
+    3.0 / (16.0 * std::f64::consts::PI) * (1.0 + cos_theta * cos_theta)
+}
+
+// direction around the frame's w axis with the given cosine and a uniformly random azimuth
+fn direction_from_cosine(uvw: &Onb, cos_theta: f64) -> Vec3 {
+    let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+    let phi = 2.0 * std::f64::consts::PI * random_double();
+
+    uvw.transform(Vec3::new(f64::cos(phi) * sin_theta, f64::sin(phi) * sin_theta, cos_theta))
+}
+
+pub struct HenyeyGreensteinPdf {
+    uvw: Onb,
+    g: f64
+}
+
+impl HenyeyGreensteinPdf {
+    // w is the direction the light was travelling before scattering
+    pub fn new(w: Vec3, g: f64) -> HenyeyGreensteinPdf {
+        HenyeyGreensteinPdf {
+            uvw: Onb::new(&w),
+            g
+        }
+    }
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        henyey_greenstein(dot(unit_vector(direction), self.uvw.w()), self.g)
+    }
+
+    fn generate(&self) -> Vec3 {
+        let cos_theta = if f64::abs(self.g) < 1e-3 {
+            1.0 - 2.0 * random_double()
+        } else {
+            let sq = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * random_double());
+            (1.0 + self.g * self.g - sq * sq) / (2.0 * self.g)
+        };
+
+        direction_from_cosine(&self.uvw, f64::clamp(cos_theta, -1.0, 1.0))
+    }
+}
+
+pub struct DoubleHenyeyGreensteinPdf {
+    forward: HenyeyGreensteinPdf,
+    backward: HenyeyGreensteinPdf,
+    weight: f64
+}
+
+impl DoubleHenyeyGreensteinPdf {
+    // weight is the share of the forward lobe
+    pub fn new(w: Vec3, g_forward: f64, g_backward: f64, weight: f64) -> DoubleHenyeyGreensteinPdf {
+        DoubleHenyeyGreensteinPdf {
+            forward: HenyeyGreensteinPdf::new(w, g_forward),
+            backward: HenyeyGreensteinPdf::new(w, g_backward),
+            weight
+        }
+    }
+}
+
+impl Pdf for DoubleHenyeyGreensteinPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        self.weight * self.forward.value(direction) + (1.0 - self.weight) * self.backward.value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        if random_double() < self.weight {
+            self.forward.generate()
+        } else {
+            self.backward.generate()
+        }
+    }
+}
+
+pub struct RayleighPdf {
+    uvw: Onb
+}
+
+impl RayleighPdf {
+    pub fn new(w: Vec3) -> RayleighPdf {
+        RayleighPdf {
+            uvw: Onb::new(&w)
+        }
+    }
+}
+
+impl Pdf for RayleighPdf {
+    fn value(&self, direction: Vec3) -> f64 {
+        rayleigh(dot(unit_vector(direction), self.uvw.w()))
+    }
+
+    fn generate(&self) -> Vec3 {
+        // invert the cdf (cos^3 + 3cos + 4) / 8 with Cardano's formula
+        let q = 4.0 - 8.0 * random_double();
+        let a = f64::cbrt(-q / 2.0 + f64::sqrt(q * q / 4.0 + 1.0));
+        let cos_theta = a - 1.0 / a;
+
+        direction_from_cosine(&self.uvw, f64::clamp(cos_theta, -1.0, 1.0))
+    }
+}
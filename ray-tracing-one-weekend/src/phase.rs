@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use crate::{color::Color, hittable::HitRecord, material::{Material, ScatterRecord}, pdf::{self, DoubleHenyeyGreensteinPdf, HenyeyGreensteinPdf, Pdf, RayleighPdf}, ray::Ray, texture::{SolidColor, Texture}, vec3::{dot, unit_vector}};
+
+pub struct HenyeyGreenstein {
+    albedo: Box<dyn Texture>,
+    g: f64
+}
+
+impl HenyeyGreenstein {
+    // g in (-1, 1): positive scatters forward, negative backward, zero is isotropic
+    pub fn new(albedo: Box<dyn Texture>, g: f64) -> Self {
+        HenyeyGreenstein {
+            albedo,
+            g: f64::clamp(g, -0.999, 0.999)
+        }
+    }
+
+    pub fn from_color(albedo_color: Color, g: f64) -> Self {
+        HenyeyGreenstein::new(Box::new(SolidColor::new(albedo_color)), g)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let pdf = HenyeyGreensteinPdf::new(r_in.direction(), self.g);
+
+        Some(ScatterRecord {
+            attenuation: self.albedo.get_color(rec.u, rec.v, &rec.p),
+            scattered: Ray::new(rec.p, pdf.generate(), r_in.time()),
+            pdf: Some(Arc::new(pdf)),
+            lobe: None
+        })
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(unit_vector(r_in.direction()), unit_vector(scattered.direction()));
+        pdf::henyey_greenstein(cos_theta, self.g)
+    }
+}
+
+pub struct DoubleHenyeyGreenstein {
+    albedo: Box<dyn Texture>,
+    g_forward: f64,
+    g_backward: f64,
+    weight: f64
+}
+
+impl DoubleHenyeyGreenstein {
+    // a forward lobe blended with a backward lobe, weight being the share of the forward one
+    pub fn new(albedo: Box<dyn Texture>, g_forward: f64, g_backward: f64, weight: f64) -> Self {
+        DoubleHenyeyGreenstein {
+            albedo,
+            g_forward: f64::clamp(g_forward, -0.999, 0.999),
+            g_backward: f64::clamp(g_backward, -0.999, 0.999),
+            weight: f64::clamp(weight, 0.0, 1.0)
+        }
+    }
+
+    pub fn from_color(albedo_color: Color, g_forward: f64, g_backward: f64, weight: f64) -> Self {
+        DoubleHenyeyGreenstein::new(Box::new(SolidColor::new(albedo_color)), g_forward, g_backward, weight)
+    }
+}
+
+impl Material for DoubleHenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let pdf = DoubleHenyeyGreensteinPdf::new(r_in.direction(), self.g_forward, self.g_backward, self.weight);
+
+        Some(ScatterRecord {
+            attenuation: self.albedo.get_color(rec.u, rec.v, &rec.p),
+            scattered: Ray::new(rec.p, pdf.generate(), r_in.time()),
+            pdf: Some(Arc::new(pdf)),
+            lobe: None
+        })
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(unit_vector(r_in.direction()), unit_vector(scattered.direction()));
+        self.weight * pdf::henyey_greenstein(cos_theta, self.g_forward) + (1.0 - self.weight) * pdf::henyey_greenstein(cos_theta, self.g_backward)
+    }
+}
+
+pub struct Rayleigh {
+    albedo: Box<dyn Texture>
+}
+
+impl Rayleigh {
+    pub fn new(albedo: Box<dyn Texture>) -> Self {
+        Rayleigh {
+            albedo
+        }
+    }
+
+    pub fn from_color(albedo_color: Color) -> Self {
+        Rayleigh::new(Box::new(SolidColor::new(albedo_color)))
+    }
+}
+
+impl Material for Rayleigh {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let pdf = RayleighPdf::new(r_in.direction());
+
+        Some(ScatterRecord {
+            attenuation: self.albedo.get_color(rec.u, rec.v, &rec.p),
+            scattered: Ray::new(rec.p, pdf.generate(), r_in.time()),
+            pdf: Some(Arc::new(pdf)),
+            lobe: None
+        })
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, _rec: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = dot(unit_vector(r_in.direction()), unit_vector(scattered.direction()));
+        pdf::rayleigh(cos_theta)
+    }
+}
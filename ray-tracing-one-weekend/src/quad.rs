@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{common::{self, random_double}, hittable::{self, HitRecord, Hittable}, hittable_list::HittableList, material::Material, ray::Ray, vec2::Vec2, vec3::{self, cross, dot, unit_vector, Point3, Vec3}};
+use crate::{aabb::AABB, common::random_double, hittable::{self, HitRecord, Hittable}, hittable_list::HittableList, light::{emitted_power, sample_emitter, Light, LightList, LightSample}, light_bvh::LightBounds, material::Material, ray::Ray, vec2::Vec2, vec3::{self, cross, dot, unit_vector, Point3, Vec3}};
 
 pub struct Quad {
     q: Point3,
@@ -91,13 +91,35 @@ impl Hittable for Quad {
         Some(rec)
     }
 
-    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
-        let rec = self.hit(&Ray::new(origin, direction, 0.0), 0.001, common::INFINITY);
-        if let None = rec {
-            return 0.0;
+
+    fn bounding_box(&self) -> Option<AABB> {
+        Some(AABB::from_sub_aabbs(
+            AABB::from_points(self.q, self.q + self.u + self.v),
+            AABB::from_points(self.q + self.u, self.q + self.v),
+        ))
+    }
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        if self.mat.is_emissive() {
+            lights.add(self);
         }
+    }
+}
+
+impl Light for Quad {
+    // samples a point uniformly over the quad's area
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let p = self.q + (random_double() * self.u) + (random_double() * self.v);
+        let direction = p - point;
+
+        sample_emitter(self, point, direction, time, self.pdf_li(point, direction, time))
+    }
 
-        let rec = rec.expect("Should have a hit record");
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let rec = match self.hit(&Ray::new(point, direction, time), 0.001, f64::INFINITY) {
+            Some(rec) => rec,
+            None => return 0.0
+        };
 
         let distance_squared = rec.t * rec.t * direction.length_squared();
         let cosine = f64::abs(dot(direction, rec.normal) / direction.length());
@@ -105,8 +127,26 @@ impl Hittable for Quad {
         distance_squared / (cosine * self.area)
     }
 
-    fn random(&self, origin: Point3) -> Vec3 {
-        let p = self.q + (random_double() * self.u) + (random_double() * self.v);
-        p - origin
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn power(&self) -> f64 {
+        let mut spots = Vec::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                let (alpha, beta) = ((i as f64 + 0.5) / 3.0, (j as f64 + 0.5) / 3.0);
+                spots.push((self.q + alpha * self.u + beta * self.v, self.normal, alpha, beta));
+            }
+        }
+
+        emitted_power(&self.mat, self.area, &spots)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let bounds = self.bounding_box()?;
+
+        let spread = self.mat.emission_spread();
+        Some(LightBounds::new(bounds, self.normal, 1.0, spread.cos_theta_e, self.power(), spread.two_sided))
     }
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+use crate::{common::degrees_to_radians, mat4::Mat4, vec3::{dot, unit_vector, Vec3}};
+
+// A unit quaternion standing for a rotation, so that rotations can be blended smoothly
+#[derive(Clone, Copy)]
+pub struct Quaternion {
+    w: f64,
+    v: Vec3
+}
+
+impl Quaternion {
+    pub fn new(w: f64, v: Vec3) -> Quaternion {
+        let length = f64::sqrt(w * w + v.length_squared());
+        Quaternion {
+            w: w / length,
+            v: v / length
+        }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(1.0, Vec3::new(0.0, 0.0, 0.0))
+    }
+
+    // the same rotation as Mat4::rotation
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Quaternion {
+        let half = 0.5 * degrees_to_radians(angle);
+        Quaternion::new(f64::cos(half), f64::sin(half) * unit_vector(axis))
+    }
+
+    pub fn dot(a: Quaternion, b: Quaternion) -> f64 {
+        a.w * b.w + dot(a.v, b.v)
+    }
+
+    // angle in radians of the rotation taking a to b
+    pub fn angle_between(a: Quaternion, b: Quaternion) -> f64 {
+        2.0 * f64::acos(f64::min(f64::abs(Quaternion::dot(a, b)), 1.0))
+    }
+
+    // constant speed rotation from a to b as t goes from 0 to 1, the short way round
+    pub fn slerp(a: Quaternion, b: Quaternion, t: f64) -> Quaternion {
+        let mut cos_theta = Quaternion::dot(a, b);
+        let b = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            Quaternion { w: -b.w, v: -b.v }
+        } else {
+            b
+        };
+
+        // nearly the same rotation, where slerp would divide by almost nothing
+        if cos_theta > 0.9995 {
+            return Quaternion::new(a.w + t * (b.w - a.w), a.v + t * (b.v - a.v));
+        }
+
+        let theta = f64::acos(cos_theta);
+        let wa = f64::sin((1.0 - t) * theta) / f64::sin(theta);
+        let wb = f64::sin(t * theta) / f64::sin(theta);
+
+        Quaternion::new(wa * a.w + wb * b.w, wa * a.v + wb * b.v)
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion {
+            w: self.w,
+            v: -self.v
+        }
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        let (w, x, y, z) = (self.w, self.v.x(), self.v.y(), self.v.z());
+
+        Mat4::new([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y), 0.0],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x), 0.0],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_rotation(a: Quaternion, b: Quaternion) -> bool {
+        f64::abs(Quaternion::dot(a, b)) > 1.0 - 1e-9
+    }
+
+    #[test]
+    fn slerp_hits_the_endpoints_at_constant_speed() {
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let (a, b) = (Quaternion::from_axis_angle(axis, 10.0), Quaternion::from_axis_angle(axis, 100.0));
+
+        assert!(same_rotation(Quaternion::slerp(a, b, 0.0), a));
+        assert!(same_rotation(Quaternion::slerp(a, b, 1.0), b));
+        assert!(same_rotation(Quaternion::slerp(a, b, 0.5), Quaternion::from_axis_angle(axis, 55.0)));
+        assert!(same_rotation(Quaternion::slerp(a, b, 0.25), Quaternion::from_axis_angle(axis, 32.5)));
+    }
+
+    #[test]
+    fn slerp_takes_the_short_way_round() {
+        let axis = Vec3::new(1.0, 1.0, 0.0);
+        let (a, b) = (Quaternion::from_axis_angle(axis, 10.0), Quaternion::from_axis_angle(axis, 100.0));
+        // the same rotation as b from the other side of the sphere of quaternions
+        let flipped = Quaternion { w: -b.w, v: -b.v };
+
+        assert!(same_rotation(Quaternion::slerp(a, flipped, 0.5), Quaternion::from_axis_angle(axis, 55.0)));
+    }
+
+    #[test]
+    fn slerp_between_nearly_equal_rotations_stays_unit() {
+        let axis = Vec3::new(0.0, 0.0, 1.0);
+        let (a, b) = (Quaternion::from_axis_angle(axis, 10.0), Quaternion::from_axis_angle(axis, 10.0001));
+        let mid = Quaternion::slerp(a, b, 0.5);
+
+        assert!(f64::abs(Quaternion::dot(mid, mid) - 1.0) < 1e-12);
+        assert!(same_rotation(mid, Quaternion::from_axis_angle(axis, 10.00005)));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{background::Background, color::Color, common::{degrees_to_radians, random_double}, light::{luminance, Light, LightSample}, light_bvh::LightBounds, onb::Onb, vec3::{dot, unit_vector, Point3, Vec3}};
+
+// scale from kcd/m^2 to the renderer's radiance units, keeping a clear midday sky below 1
+const LUMINANCE_SCALE: f64 = 0.05;
+// luminance of the sun above the atmosphere in kcd/m^2, and its angular radius in degrees
+const SUN_LUMINANCE: f64 = 2.0e6;
+const SUN_ANGULAR_RADIUS: f64 = 0.2667;
+
+// Perez's sky luminance distribution, relative to the zenith
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64
+}
+
+impl Perez {
+    fn value(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let cos_gamma = f64::cos(gamma);
+        (1.0 + self.a * f64::exp(self.b / cos_theta)) * (1.0 + self.c * f64::exp(self.d * gamma) + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+// Preetham, Shirley and Smits' analytic daylight model with a sun disk
+pub struct Sky {
+    sun_direction: Vec3,
+    cos_sun_radius: f64,
+    sun_radiance: Color,
+    ground: Color,
+    perez: [Perez; 3],
+    // zenith value divided by the perez function at the zenith, for Y, x and y
+    zenith: [f64; 3]
+}
+
+impl Sky {
+    // turbidity runs from about 2 (very clear) to 10 (hazy)
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color) -> Sky {
+        let sun_direction = unit_vector(sun_direction);
+        let t = turbidity;
+        let theta_s = f64::acos(f64::clamp(sun_direction.y(), -1.0, 1.0));
+        // the model breaks down with the sun below the horizon, so hold it there
+        let theta_s_clamped = f64::min(theta_s, std::f64::consts::FRAC_PI_2);
+
+        let perez = [
+            Perez { a: 0.1787 * t - 1.4630, b: -0.3554 * t + 0.4275, c: -0.0227 * t + 5.3251, d: 0.1206 * t - 2.5771, e: -0.0670 * t + 0.3703 },
+            Perez { a: -0.0193 * t - 0.2592, b: -0.0665 * t + 0.0008, c: -0.0004 * t + 0.2125, d: -0.0641 * t - 0.8989, e: -0.0033 * t + 0.0452 },
+            Perez { a: -0.0167 * t - 0.2608, b: -0.0950 * t + 0.0092, c: -0.0079 * t + 0.2102, d: -0.0441 * t - 1.6537, e: -0.0109 * t + 0.0529 },
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f64::consts::PI - 2.0 * theta_s_clamped);
+        let zenith_luminance = f64::max(0.0, (4.0453 * t - 4.9710) * f64::tan(chi) - 0.2155 * t + 2.4192);
+
+        let s = theta_s_clamped;
+        let (s2, s3) = (s * s, s * s * s);
+        let zenith_x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let zenith_y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+
+        let zenith = [
+            zenith_luminance / perez[0].value(1.0, theta_s_clamped),
+            zenith_x / perez[1].value(1.0, theta_s_clamped),
+            zenith_y / perez[2].value(1.0, theta_s_clamped),
+        ];
+
+        let sun_radiance = if theta_s < std::f64::consts::FRAC_PI_2 {
+            SUN_LUMINANCE * LUMINANCE_SCALE * Self::sun_transmittance(theta_s, t)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        };
+        let cos_sun_radius = f64::cos(degrees_to_radians(SUN_ANGULAR_RADIUS));
+
+        let mut sky = Sky {
+            sun_direction,
+            cos_sun_radius,
+            sun_radiance,
+            ground: Color::new(0.0, 0.0, 0.0),
+            perez,
+            zenith
+        };
+
+        // lambertian ground lit by the sun and, roughly, a sky as bright as its zenith all over
+        let sun_solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_sun_radius);
+        let irradiance = std::f64::consts::PI * sky.sky_color(Vec3::new(0.0, 1.0, 0.0)) + sun_solid_angle * f64::max(sun_direction.y(), 0.0) * sun_radiance;
+        sky.ground = ground_albedo * irradiance / std::f64::consts::PI;
+
+        sky
+    }
+
+    // fraction of sunlight reaching the ground per channel after rayleigh and aerosol scattering
+    fn sun_transmittance(theta_s: f64, turbidity: f64) -> Color {
+        let zenith_degrees = theta_s.to_degrees();
+        let air_mass = 1.0 / (f64::cos(theta_s) + 0.15 * f64::powf(93.885 - zenith_degrees, -1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+
+        let channel = |wavelength_um: f64| {
+            let rayleigh = 0.008735 * f64::powf(wavelength_um, -4.08);
+            let aerosol = beta * f64::powf(wavelength_um, -1.3);
+            f64::exp(-(rayleigh + aerosol) * air_mass)
+        };
+
+        Color::new(channel(0.65), channel(0.55), channel(0.45))
+    }
+
+    fn sky_color(&self, direction: Vec3) -> Color {
+        let cos_theta = f64::max(direction.y(), 0.01);
+        let gamma = f64::acos(f64::clamp(dot(direction, self.sun_direction), -1.0, 1.0));
+
+        let luminance = self.zenith[0] * self.perez[0].value(cos_theta, gamma);
+        let x = self.zenith[1] * self.perez[1].value(cos_theta, gamma);
+        let y = self.zenith[2] * self.perez[2].value(cos_theta, gamma);
+
+        // xyY to linear sRGB
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        let r = 3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z;
+        let g = -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z;
+        let b = 0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z;
+
+        LUMINANCE_SCALE * Color::new(f64::max(r, 0.0), f64::max(g, 0.0), f64::max(b, 0.0))
+    }
+
+    // once the whole disk has set the ground hides it from every direction
+    fn sun_has_set(&self) -> bool {
+        let sin_sun_radius = f64::sqrt(1.0 - self.cos_sun_radius * self.cos_sun_radius);
+        self.sun_direction.y() < -sin_sun_radius
+    }
+}
+
+impl Background for Sky {
+    fn color(&self, direction: Vec3) -> Color {
+        let direction = unit_vector(direction);
+        if direction.y() < 0.0 {
+            return self.ground;
+        }
+
+        let sun = if dot(direction, self.sun_direction) > self.cos_sun_radius {
+            self.sun_radiance
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        };
+
+        self.sky_color(direction) + sun
+    }
+}
+
+// As a light the sky samples the sun disk, leaving the rest of the dome to scattered rays
+impl Light for Sky {
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        if self.sun_has_set() {
+            return None;
+        }
+
+        let z = 1.0 + random_double() * (self.cos_sun_radius - 1.0);
+        let phi = 2.0 * std::f64::consts::PI * random_double();
+        let sin_theta = f64::sqrt(1.0 - z * z);
+        let direction = Onb::new(&self.sun_direction).transform(Vec3::new(f64::cos(phi) * sin_theta, f64::sin(phi) * sin_theta, z));
+
+        Some(LightSample {
+            direction,
+            radiance: self.color(direction),
+            pdf: self.pdf_li(point, direction, time),
+            distance: f64::INFINITY
+        })
+    }
+
+    fn pdf_li(&self, _point: Point3, direction: Vec3, _time: f64) -> f64 {
+        if self.sun_has_set() {
+            return 0.0;
+        }
+
+        if dot(unit_vector(direction), self.sun_direction) > self.cos_sun_radius {
+            1.0 / (2.0 * std::f64::consts::PI * (1.0 - self.cos_sun_radius))
+        } else {
+            0.0
+        }
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    // irradiance from the sun and, roughly, the sky as bright as its zenith everywhere
+    fn power(&self) -> f64 {
+        let sun_solid_angle = 2.0 * std::f64::consts::PI * (1.0 - self.cos_sun_radius);
+        std::f64::consts::PI * luminance(self.sky_color(Vec3::new(0.0, 1.0, 0.0))) + sun_solid_angle * luminance(self.sun_radiance)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        None
+    }
+}
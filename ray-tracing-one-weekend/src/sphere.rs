@@ -1,7 +1,6 @@
-use core::f64;
 use std::sync::Arc;
 
-use crate::{common::random_double, hittable::{HitRecord, Hittable}, material::Material, onb::Onb, ray::Ray, vec2::UV, vec3::{self, Point3, Vec3}};
+use crate::{aabb::AABB, common::random_double, hittable::{HitRecord, Hittable}, light::{emitted_power, sample_emitter, Light, LightList, LightSample}, light_bvh::LightBounds, material::Material, onb::Onb, ray::Ray, vec2::UV, vec3::{self, random_unit_vector, Point3, Vec3}};
 
 pub struct Sphere {
     center: Ray,
@@ -20,10 +19,10 @@ impl Sphere {
 
     fn get_sphere_uv(p: Point3) -> UV {
         let theta = f64::acos(-p.y());
-        let phi = f64::atan2(-p.z(), p.x()) + f64::consts::PI;
+        let phi = f64::atan2(-p.z(), p.x()) + std::f64::consts::PI;
 
-        let u = phi / (2.0 * f64::consts::PI);
-        let v = theta / f64::consts::PI;
+        let u = phi / (2.0 * std::f64::consts::PI);
+        let v = theta / std::f64::consts::PI;
 
         UV::new(u, v)
     }
@@ -33,7 +32,7 @@ impl Sphere {
         let r2 = random_double();
         let z = 1.0 + r2 * (f64::sqrt(1.0 - (radius * radius)/distance_squared) - 1.0);
 
-        let phi = 2.0 * f64::consts::PI * r1;
+        let phi = 2.0 * std::f64::consts::PI * r1;
         let x = f64::cos(phi) * f64::sqrt(1.0 - z * z);
         let y = f64::sin(phi) * f64::sqrt(1.0 - z * z);
 
@@ -82,23 +81,76 @@ impl Hittable for Sphere {
         Some(rec)
     }
 
-    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
-        if let None = self.hit(&Ray::new(origin, direction, 0.0), 0.001, f64::INFINITY) {
+
+    // anywhere along the sphere's motion over the shutter interval
+    fn bounding_box(&self) -> Option<AABB> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        let (start, end) = (self.center.at(0.0), self.center.at(1.0));
+        Some(AABB::from_sub_aabbs(AABB::from_points(start - extent, start + extent), AABB::from_points(end - extent, end + extent)))
+    }
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        if self.mat.is_emissive() {
+            lights.add(self);
+        }
+    }
+}
+
+impl Light for Sphere {
+    // samples the cone of directions the sphere covers as seen from point, where it is at time
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let direction = self.center.at(time) - point;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return sample_emitter(self, point, random_unit_vector(), time, 1.0 / (4.0 * std::f64::consts::PI));
+        }
+
+        let uvw = Onb::new(&direction);
+        let sampled = uvw.transform(Sphere::random_to_sphere(self.radius, distance_squared));
+
+        sample_emitter(self, point, sampled, time, self.pdf_li(point, sampled, time))
+    }
+
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let dist_squared = (self.center.at(time) - point).length_squared();
+        if dist_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * std::f64::consts::PI);
+        }
+
+        if self.hit(&Ray::new(point, direction, time), 0.001, f64::INFINITY).is_none() {
             return 0.0;
         }
 
-        let dist_squared = (self.center.at(0.0) - origin).length_squared();
         let cos_theta_max = f64::sqrt(1.0 - self.radius * self.radius / dist_squared);
-        let solid_angle = 2.0 * f64::consts::PI * (1.0 - cos_theta_max);
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_theta_max);
 
         1.0 / solid_angle
     }
 
-    fn random(&self, origin: Point3) -> Vec3 {
-        let direction = self.center.at(0.0) - origin;
-        let distance_squared = direction.length_squared();
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn power(&self) -> f64 {
+        let center = self.center.at(0.0);
+        let spots: Vec<(Point3, Vec3, f64, f64)> = [
+            Vec3::new(1.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0),
+        ].into_iter().map(|normal| {
+            let uv = Sphere::get_sphere_uv(normal);
+            (center + self.radius * normal, normal, uv.x(), uv.y())
+        }).collect();
+
+        emitted_power(&self.mat, 4.0 * std::f64::consts::PI * self.radius * self.radius, &spots)
+    }
+
+    // normals point every way, each emitting over its hemisphere, from anywhere along the
+    // sphere's motion over the shutter interval
+    fn bounds(&self) -> Option<LightBounds> {
+        let bounds = self.bounding_box()?;
 
-        let uwu = Onb::new(&direction);
-        uwu.transform(Sphere::random_to_sphere(self.radius, distance_squared))
+        let spread = self.mat.emission_spread();
+        Some(LightBounds::new(bounds, Vec3::new(0.0, 0.0, 1.0), -1.0, spread.cos_theta_e, self.power(), spread.two_sided))
     }
 }
\ No newline at end of file
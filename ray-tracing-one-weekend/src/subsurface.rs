@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use crate::{aabb::AABB, color::Color, common::{random_double, random_int_range}, constant_medium::volume_hit_record, hittable::{HitRecord, Hittable}, light::LightList, material::{Dielectric, Interior, Isotropic, Material, ScatterRecord}, ray::Ray, vec3::Point3};
+
+pub struct Subsurface {
+    boundary: Arc<dyn Hittable>,
+    interface: Arc<dyn Material>,
+    phase_function: Arc<dyn Material>,
+    scattering: Color,
+    extinction: Color
+}
+
+impl Subsurface {
+    // coefficients are per unit length, one per color channel
+    pub fn new(boundary: Arc<dyn Hittable>, ior: f64, scattering: Color, absorption: Color) -> Self {
+        Subsurface {
+            boundary,
+            interface: Arc::new(Dielectric::new(ior)),
+            phase_function: Arc::new(Isotropic::from_color(Color::new(1.0, 1.0, 1.0))),
+            scattering,
+            extinction: scattering + absorption
+        }
+    }
+
+    pub fn from_mean_free_path(boundary: Arc<dyn Hittable>, ior: f64, albedo: Color, mean_free_path: Color) -> Self {
+        let extinction = Color::new(1.0 / mean_free_path.x(), 1.0 / mean_free_path.y(), 1.0 / mean_free_path.z());
+        let scattering = albedo * extinction;
+
+        Subsurface::new(boundary, ior, scattering, extinction - scattering)
+    }
+
+    fn transmittance(&self, distance: f64) -> Color {
+        Color::new(
+            f64::exp(-self.extinction.x() * distance),
+            f64::exp(-self.extinction.y() * distance),
+            f64::exp(-self.extinction.z() * distance),
+        )
+    }
+
+    fn average(c: Color) -> f64 {
+        (c.x() + c.y() + c.z()) / 3.0
+    }
+}
+
+impl Hittable for Subsurface {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let mut surface_rec = self.boundary.hit(ray, t_min, f64::INFINITY)?;
+
+        // entering from outside only meets the dielectric boundary
+        if surface_rec.front_face {
+            if surface_rec.t > t_max {
+                return None;
+            }
+            surface_rec.mat = self.interface.clone();
+            return Some(surface_rec);
+        }
+
+        // inside, walk towards the exit sampling distances from a randomly chosen channel,
+        // reweighting by the pdf averaged over all channels. Distances are measured from t_min,
+        // where the walk can first collide, so no collision is lost to the offset.
+        let ray_length = ray.direction().length();
+        let distance_to_exit = (surface_rec.t - t_min) * ray_length;
+        let channel_extinction = match random_int_range(0, 2) {
+            0 => self.extinction.x(),
+            1 => self.extinction.y(),
+            _ => self.extinction.z()
+        };
+        let hit_distance = -f64::ln(random_double()) / channel_extinction;
+
+        if hit_distance < distance_to_exit {
+            let t = t_min + hit_distance / ray_length;
+            if t > t_max {
+                return None;
+            }
+
+            let transmittance = self.transmittance(hit_distance);
+            let pdf = Self::average(self.extinction * transmittance);
+            let weight = self.scattering * transmittance / pdf;
+
+            return Some(volume_hit_record(ray, t, Arc::new(Weighted::new(self.phase_function.clone(), weight))));
+        }
+
+        if surface_rec.t > t_max {
+            return None;
+        }
+
+        let transmittance = self.transmittance(distance_to_exit);
+        let weight = transmittance / Self::average(transmittance);
+        surface_rec.mat = Arc::new(Weighted::new(self.interface.clone(), weight));
+
+        Some(surface_rec)
+    }
+
+    // the walk never leaves the boundary
+    fn bounding_box(&self) -> Option<AABB> {
+        self.boundary.bounding_box()
+    }
+
+    // light leaving the surface is scattered from inside rather than emitted
+    fn collect_lights(self: Arc<Self>, _lights: &mut LightList) {}
+}
+
+// scales the attenuation of another material by the throughput carried by the random walk
+struct Weighted {
+    inner: Arc<dyn Material>,
+    weight: Color
+}
+
+impl Weighted {
+    fn new(inner: Arc<dyn Material>, weight: Color) -> Self {
+        Weighted {
+            inner,
+            weight
+        }
+    }
+}
+
+impl Material for Weighted {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let mut scatter_rec = self.inner.scatter(r_in, rec)?;
+        scatter_rec.attenuation *= self.weight;
+        Some(scatter_rec)
+    }
+
+    fn emitted(&self, r_in: &Ray, hit_rec: &HitRecord, u: f64, v: f64, p: &Point3) -> Color {
+        self.weight * self.inner.emitted(r_in, hit_rec, u, v, p)
+    }
+
+    fn scatter_pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> f64 {
+        self.inner.scatter_pdf(r_in, rec, scattered)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        self.inner.interior()
+    }
+
+    fn scatter_between(&self, r_in: &Ray, rec: &HitRecord, incident_ior: f64, transmitted_ior: f64) -> Option<ScatterRecord> {
+        let mut scatter_rec = self.inner.scatter_between(r_in, rec, incident_ior, transmitted_ior)?;
+        scatter_rec.attenuation *= self.weight;
+        Some(scatter_rec)
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{color::Color, hittable::HitRecord, texture::{SolidColor, Texture}};
+
+// representative wavelengths (nm) for the red, green and blue channels
+const WAVELENGTHS: [f64; 3] = [650.0, 532.0, 450.0];
+
+pub struct ThinFilm {
+    thickness: Box<dyn Texture>,
+    ior: f64
+}
+
+impl ThinFilm {
+    // thickness is in nanometres, read from the red channel of the texture
+    pub fn new(thickness: Box<dyn Texture>, ior: f64) -> ThinFilm {
+        ThinFilm {
+            thickness,
+            ior
+        }
+    }
+
+    pub fn from_thickness(thickness: f64, ior: f64) -> ThinFilm {
+        let thickness = SolidColor::from_rgb(thickness, thickness, thickness);
+
+        ThinFilm {
+            thickness: Box::new(thickness),
+            ior
+        }
+    }
+
+    // reflectance of the film sitting between a medium of outside_ior and a dielectric substrate
+    pub fn reflectance(&self, cos_theta: f64, outside_ior: f64, substrate_ior: f64, rec: &HitRecord) -> Color {
+        let sin_theta_sq = 1.0 - cos_theta * cos_theta;
+        let sin_substrate_sq = (outside_ior / substrate_ior) * (outside_ior / substrate_ior) * sin_theta_sq;
+        let cos_film = match self.film_cosine(cos_theta, outside_ior) {
+            Some(cos_film) if sin_substrate_sq < 1.0 => cos_film,
+            _ => return Color::new(1.0, 1.0, 1.0)
+        };
+        let cos_substrate = f64::sqrt(1.0 - sin_substrate_sq);
+
+        let r23 = fresnel_amplitudes(self.ior, cos_film, substrate_ior, cos_substrate);
+        self.interfere(cos_theta, cos_film, outside_ior, [r23; 3], rec)
+    }
+
+    // reflectance of the film over a conductor, treating the albedo as its normal-incidence reflectance
+    pub fn conductor_reflectance(&self, cos_theta: f64, outside_ior: f64, albedo: Color, rec: &HitRecord) -> Color {
+        let cos_film = match self.film_cosine(cos_theta, outside_ior) {
+            Some(cos_film) => cos_film,
+            None => return Color::new(1.0, 1.0, 1.0)
+        };
+
+        // conductors flip the phase on reflection, like a very dense dielectric
+        let r23 = |channel: f64| {
+            let r = -f64::sqrt(f64::clamp(channel, 0.0, 1.0));
+            (r, r)
+        };
+
+        self.interfere(cos_theta, cos_film, outside_ior, [r23(albedo.x()), r23(albedo.y()), r23(albedo.z())], rec)
+    }
+
+    fn film_cosine(&self, cos_theta: f64, outside_ior: f64) -> Option<f64> {
+        let sin_theta_sq = 1.0 - cos_theta * cos_theta;
+        let sin_film_sq = (outside_ior / self.ior) * (outside_ior / self.ior) * sin_theta_sq;
+        if sin_film_sq >= 1.0 {
+            return None;
+        }
+
+        Some(f64::sqrt(1.0 - sin_film_sq))
+    }
+
+    fn interfere(&self, cos_theta: f64, cos_film: f64, outside_ior: f64, r23: [(f64, f64); 3], rec: &HitRecord) -> Color {
+        let (r12_s, r12_p) = fresnel_amplitudes(outside_ior, cos_theta, self.ior, cos_film);
+
+        let thickness = f64::max(self.thickness.get_color(rec.u, rec.v, &rec.p).x(), 0.0);
+        let optical_path = 2.0 * self.ior * thickness * cos_film;
+
+        let mut reflectance = [0.0; 3];
+        for (i, (wavelength, (r23_s, r23_p))) in WAVELENGTHS.iter().zip(r23).enumerate() {
+            let cos_phase = f64::cos(2.0 * std::f64::consts::PI * optical_path / wavelength);
+            reflectance[i] = 0.5 * (airy(r12_s, r23_s, cos_phase) + airy(r12_p, r23_p, cos_phase));
+        }
+
+        Color::new(reflectance[0], reflectance[1], reflectance[2])
+    }
+}
+
+// s and p polarised amplitude reflection coefficients
+fn fresnel_amplitudes(n_i: f64, cos_i: f64, n_t: f64, cos_t: f64) -> (f64, f64) {
+    let r_s = (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t);
+    let r_p = (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t);
+
+    (r_s, r_p)
+}
+
+// reflectance of two interfaces whose reflections interfere with the given phase difference
+fn airy(r12: f64, r23: f64, cos_phase: f64) -> f64 {
+    let cross_term = 2.0 * r12 * r23 * cos_phase;
+    let numerator = r12 * r12 + r23 * r23 + cross_term;
+    let denominator = 1.0 + r12 * r12 * r23 * r23 + cross_term;
+
+    f64::clamp(numerator / denominator, 0.0, 1.0)
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{common::degrees_to_radians, hittable::Hittable, ray::Ray, vec3::{Point3, Vec3}};
+use crate::{aabb::AABB, common::degrees_to_radians, hittable::{HitRecord, Hittable}, light::{Light, LightList, LightSample}, light_bvh::LightBounds, mat4::Mat4, quaternion::Quaternion, ray::Ray, vec3::{cross, dot, unit_vector, Point3, Vec3}};
 
 pub struct Translate {
     object: Arc<dyn Hittable>,
@@ -28,6 +28,61 @@ impl Hittable for Translate {
             }
          }
     }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        self.object.transmittance(&Ray::new(ray.origin() - self.offset, ray.direction(), ray.time()), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        let bounds = self.object.bounding_box()?;
+        Some(AABB::from_points(bounds.min() + self.offset, bounds.max() + self.offset))
+    }
+
+    // the object's lights, moved along with it
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        let mut object_lights = LightList::new();
+        self.object.clone().collect_lights(&mut object_lights);
+
+        for light in object_lights.delta_lights().iter().chain(object_lights.area_lights()) {
+            lights.add(Arc::new(TranslatedLight {
+                light: light.clone(),
+                offset: self.offset
+            }));
+        }
+    }
+}
+
+struct TranslatedLight {
+    light: Arc<dyn Light>,
+    offset: Vec3
+}
+
+impl Light for TranslatedLight {
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        self.light.sample_li(point - self.offset, time)
+    }
+
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        self.light.pdf_li(point - self.offset, direction, time)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.light.is_delta()
+    }
+
+    fn power(&self) -> f64 {
+        self.light.power()
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let light_bounds = self.light.bounds()?;
+        let bounds = AABB::from_points(light_bounds.bounds.min() + self.offset, light_bounds.bounds.max() + self.offset);
+
+        Some(LightBounds {
+            bounds,
+            ..light_bounds
+        })
+    }
 }
 
 
@@ -51,20 +106,508 @@ impl RotateY {
     }
 }
 
+// rotation from world space into the object's space and back, for points and directions alike
+fn rotate_to_object(sin_theta: f64, cos_theta: f64, v: Vec3) -> Vec3 {
+    Vec3::new((cos_theta * v.x()) - (sin_theta * v.z()), v.y(), (sin_theta * v.x()) + (cos_theta * v.z()))
+}
+
+fn rotate_to_world(sin_theta: f64, cos_theta: f64, v: Vec3) -> Vec3 {
+    Vec3::new((cos_theta * v.x()) + (sin_theta * v.z()), v.y(), (-sin_theta * v.x()) + (cos_theta * v.z()))
+}
+
 impl Hittable for RotateY {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<crate::hittable::HitRecord> {
-        let origin = Point3::new((self.cos_theta * ray.origin().x()) - (self.sin_theta * ray.origin().z()), ray.origin().y(), (self.sin_theta * ray.origin().x()) + (self.cos_theta * ray.origin().z()));
-        let direction = Vec3::new((self.cos_theta * ray.direction().x()) - (self.sin_theta * ray.direction().z()), ray.direction().y(), (self.sin_theta * ray.direction().x()) + (self.cos_theta * ray.direction().z()));
+        let origin = rotate_to_object(self.sin_theta, self.cos_theta, ray.origin());
+        let direction = rotate_to_object(self.sin_theta, self.cos_theta, ray.direction());
 
         let rotated_ray = Ray::new(origin, direction, ray.time());
 
         if let Some(mut hit_record) = self.object.hit(&rotated_ray, t_min, t_max) {
-            hit_record.p = Point3::new((self.cos_theta * hit_record.p.x()) + (self.sin_theta * hit_record.p.z()), hit_record.p.y(), (-self.sin_theta * hit_record.p.x()) + (self.cos_theta * hit_record.p.z()));
-            hit_record.normal= Point3::new((self.cos_theta * hit_record.normal.x()) + (self.sin_theta * hit_record.normal.z()), hit_record.normal.y(), (-self.sin_theta * hit_record.normal.x()) + (self.cos_theta * hit_record.normal.z()));
+            hit_record.p = rotate_to_world(self.sin_theta, self.cos_theta, hit_record.p);
+            hit_record.normal = rotate_to_world(self.sin_theta, self.cos_theta, hit_record.normal);
 
             return Some(hit_record);
         } else {
             return None;
         }
     }
-}
\ No newline at end of file
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let origin = rotate_to_object(self.sin_theta, self.cos_theta, ray.origin());
+        let direction = rotate_to_object(self.sin_theta, self.cos_theta, ray.direction());
+
+        self.object.transmittance(&Ray::new(origin, direction, ray.time()), t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        let corners = self.object.bounding_box()?.corners().map(|corner| rotate_to_world(self.sin_theta, self.cos_theta, corner));
+        Some(AABB::enclosing(&corners))
+    }
+
+    // the object's lights, turned along with it
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        let mut object_lights = LightList::new();
+        self.object.clone().collect_lights(&mut object_lights);
+
+        for light in object_lights.delta_lights().iter().chain(object_lights.area_lights()) {
+            lights.add(Arc::new(RotatedLight {
+                light: light.clone(),
+                sin_theta: self.sin_theta,
+                cos_theta: self.cos_theta
+            }));
+        }
+    }
+}
+
+// rotations keep solid angles, so only the point and directions need turning
+struct RotatedLight {
+    light: Arc<dyn Light>,
+    sin_theta: f64,
+    cos_theta: f64
+}
+
+impl Light for RotatedLight {
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let mut light_sample = self.light.sample_li(rotate_to_object(self.sin_theta, self.cos_theta, point), time)?;
+        light_sample.direction = rotate_to_world(self.sin_theta, self.cos_theta, light_sample.direction);
+
+        Some(light_sample)
+    }
+
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let point = rotate_to_object(self.sin_theta, self.cos_theta, point);
+        let direction = rotate_to_object(self.sin_theta, self.cos_theta, direction);
+
+        self.light.pdf_li(point, direction, time)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.light.is_delta()
+    }
+
+    fn power(&self) -> f64 {
+        self.light.power()
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let light_bounds = self.light.bounds()?;
+        let corners = light_bounds.bounds.corners().map(|corner| rotate_to_world(self.sin_theta, self.cos_theta, corner));
+
+        Some(LightBounds {
+            bounds: AABB::enclosing(&corners),
+            direction: rotate_to_world(self.sin_theta, self.cos_theta, light_bounds.direction),
+            ..light_bounds
+        })
+    }
+}
+
+
+// An instance of an object under any affine transform, kept with its inverse. The object is
+// shared, so one mesh can be placed many times without copying it.
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    matrix: Mat4,
+    inverse: Mat4
+}
+
+impl Transform {
+    // matrix has to be invertible, since rays are taken back into the object's space by the
+    // inverse; one that flattens the object has no inverse and panics here rather than mid render
+    pub fn new(object: Arc<dyn Hittable>, matrix: Mat4) -> Self {
+        let inverse = matrix.inverse().expect("a transform has to be invertible, this one flattens the object");
+
+        Transform {
+            object,
+            matrix,
+            inverse
+        }
+    }
+
+    // none of the factors can be zero
+    pub fn scale(object: Arc<dyn Hittable>, factors: Vec3) -> Self {
+        Transform::new(object, Mat4::scaling(factors))
+    }
+
+    pub fn rotate(object: Arc<dyn Hittable>, axis: Vec3, angle: f64) -> Self {
+        Transform::new(object, Mat4::rotation(axis, angle))
+    }
+
+    // moves the object to eye, turning its +z axis towards target, which up can't point along
+    pub fn look_at(object: Arc<dyn Hittable>, eye: Point3, target: Point3, up: Vec3) -> Self {
+        assert!(!cross(up, target - eye).near_zero(), "look_at needs an up direction away from the line of sight");
+        Transform::new(object, Mat4::look_at(eye, target, up))
+    }
+
+    // the same object with matrix applied after this transform, without nesting instances
+    pub fn then(&self, matrix: Mat4) -> Self {
+        Transform::new(self.object.clone(), matrix * self.matrix)
+    }
+}
+
+// the object seen through matrix, whose inverse takes rays into the object's space
+fn hit_transformed(object: &dyn Hittable, matrix: &Mat4, inverse: &Mat4, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    // the direction is left unnormalised so that t means the same in both spaces
+    let object_ray = Ray::new(inverse.transform_point(ray.origin()), inverse.transform_vector(ray.direction()), ray.time());
+
+    let mut hit_record = object.hit(&object_ray, t_min, t_max)?;
+    hit_record.p = matrix.transform_point(hit_record.p);
+    // normals go by the inverse transpose to stay perpendicular to the surface
+    hit_record.normal = unit_vector(inverse.transpose().transform_vector(hit_record.normal));
+
+    Some(hit_record)
+}
+
+// delta lights can't be hit, so only area lights turn up in an object
+fn collect_transformed_lights(object: Arc<dyn Hittable>, placement: Placement, lights: &mut LightList) {
+    let mut object_lights = LightList::new();
+    object.collect_lights(&mut object_lights);
+
+    for light in object_lights.area_lights() {
+        lights.add(Arc::new(TransformedLight {
+            light: light.clone(),
+            placement: placement.clone()
+        }));
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        hit_transformed(self.object.as_ref(), &self.matrix, &self.inverse, ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        let object_ray = Ray::new(self.inverse.transform_point(ray.origin()), self.inverse.transform_vector(ray.direction()), ray.time());
+        self.object.transmittance(&object_ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        let corners = self.object.bounding_box()?.corners().map(|corner| self.matrix.transform_point(corner));
+        Some(AABB::enclosing(&corners))
+    }
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        collect_transformed_lights(self.object.clone(), Placement::Fixed(Box::new((self.matrix, self.inverse))), lights);
+    }
+}
+
+// Where an object is at one moment: translated, rotated and scaled along its own axes, in
+// that order from the world's side.
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vec3, rotation: Quaternion, scale: Vec3) -> Keyframe {
+        Keyframe {
+            time,
+            translation,
+            rotation,
+            scale
+        }
+    }
+
+    fn matrices(&self) -> (Mat4, Mat4) {
+        let matrix = Mat4::translation(self.translation) * self.rotation.to_mat4() * Mat4::scaling(self.scale);
+        let inverse_scale = Vec3::new(1.0 / self.scale.x(), 1.0 / self.scale.y(), 1.0 / self.scale.z());
+        let inverse = Mat4::scaling(inverse_scale) * self.rotation.conjugate().to_mat4() * Mat4::translation(-self.translation);
+
+        (matrix, inverse)
+    }
+}
+
+// the pose between the keyframes either side of time, held still before the first and after the last
+fn interpolate(keyframes: &[Keyframe], time: f64) -> Keyframe {
+    let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+    if next == 0 {
+        return keyframes[0];
+    }
+    if next == keyframes.len() {
+        return keyframes[next - 1];
+    }
+
+    let (a, b) = (keyframes[next - 1], keyframes[next]);
+    let t = (time - a.time) / (b.time - a.time);
+
+    Keyframe {
+        time,
+        translation: a.translation + t * (b.translation - a.translation),
+        rotation: Quaternion::slerp(a.rotation, b.rotation, t),
+        scale: a.scale + t * (b.scale - a.scale)
+    }
+}
+
+// An instance whose transform follows keyframes over the shutter interval, blurring any
+// object that moves, turns or grows while the shutter is open.
+pub struct AnimatedTransform {
+    object: Arc<dyn Hittable>,
+    keyframes: Arc<Vec<Keyframe>>,
+    // around everywhere the object goes between the first keyframe and the last
+    bounds: Option<AABB>
+}
+
+impl AnimatedTransform {
+    pub fn new(object: Arc<dyn Hittable>, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "an animated transform needs at least one keyframe");
+        // each pose is inverted by dividing by its scale
+        assert!(keyframes.iter().all(|k| k.scale.x() != 0.0 && k.scale.y() != 0.0 && k.scale.z() != 0.0), "keyframe scales can't be zero along any axis");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let keyframes = Arc::new(keyframes);
+        let bounds = object.bounding_box().map(|object_bounds| swept_bounds(&object_bounds, &Placement::Animated(keyframes.clone()).poses()).padded(1e-4));
+
+        AnimatedTransform {
+            object,
+            keyframes,
+            bounds
+        }
+    }
+
+    // moving from one pose at the start of the shutter interval to another at the end
+    pub fn between(object: Arc<dyn Hittable>, start: Keyframe, end: Keyframe) -> Self {
+        AnimatedTransform::new(object, vec![start, end])
+    }
+}
+
+impl AnimatedTransform {
+    fn misses_sweep(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.bounds.is_some_and(|bounds| !bounds.hit(ray, t_min, t_max))
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        if self.misses_sweep(ray, t_min, t_max) {
+            return None;
+        }
+
+        let (matrix, inverse) = interpolate(&self.keyframes, ray.time()).matrices();
+        hit_transformed(self.object.as_ref(), &matrix, &inverse, ray, t_min, t_max)
+    }
+
+    fn transmittance(&self, ray: &Ray, t_min: f64, t_max: f64) -> f64 {
+        if self.misses_sweep(ray, t_min, t_max) {
+            return 1.0;
+        }
+
+        let (_, inverse) = interpolate(&self.keyframes, ray.time()).matrices();
+        let object_ray = Ray::new(inverse.transform_point(ray.origin()), inverse.transform_vector(ray.direction()), ray.time());
+        self.object.transmittance(&object_ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        self.bounds
+    }
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        collect_transformed_lights(self.object.clone(), Placement::Animated(self.keyframes.clone()), lights);
+    }
+}
+
+// times the bounds of an animated object are taken at besides its keyframes, padded for the arcs
+// swept in between
+const MOTION_BOUND_STEPS: usize = 16;
+
+#[derive(Clone)]
+enum Placement {
+    Fixed(Box<(Mat4, Mat4)>),
+    Animated(Arc<Vec<Keyframe>>)
+}
+
+impl Placement {
+    fn at(&self, time: f64) -> (Mat4, Mat4) {
+        match self {
+            Placement::Fixed(matrices) => **matrices,
+            Placement::Animated(keyframes) => interpolate(keyframes, time).matrices()
+        }
+    }
+
+    // the matrices to take bounds at, with how far apart the rotations of neighbouring ones are
+    fn poses(&self) -> Vec<(Mat4, Mat4, f64)> {
+        match self {
+            Placement::Fixed(matrices) => vec![(matrices.0, matrices.1, 0.0)],
+            Placement::Animated(keyframes) => {
+                // keyframes are where the translation turns, so they have to be among the poses
+                let (start, end) = (keyframes[0].time, keyframes[keyframes.len() - 1].time);
+                let mut times: Vec<f64> = (0..=MOTION_BOUND_STEPS)
+                    .map(|i| start + (end - start) * i as f64 / MOTION_BOUND_STEPS as f64)
+                    .chain(keyframes.iter().map(|keyframe| keyframe.time))
+                    .collect();
+                times.sort_by(f64::total_cmp);
+                times.dedup();
+                let poses: Vec<Keyframe> = times.iter().map(|&time| interpolate(keyframes, time)).collect();
+
+                poses.iter().enumerate().map(|(i, pose)| {
+                    let step = |j: usize| poses.get(j).map_or(0.0, |other| Quaternion::angle_between(pose.rotation, other.rotation));
+                    let swept = f64::max(step(i + 1), if i > 0 {step(i - 1)} else {0.0});
+                    let (matrix, inverse) = pose.matrices();
+                    (matrix, inverse, swept)
+                }).collect()
+            }
+        }
+    }
+}
+
+// bounds in object space carried through every pose, covering the motion between them
+fn swept_bounds(bounds: &AABB, poses: &[(Mat4, Mat4, f64)]) -> AABB {
+    let mut corners = Vec::new();
+    for (matrix, _, swept) in poses {
+        // rotations turn about the object's origin
+        let center = matrix.transform_point(Point3::new(0.0, 0.0, 0.0));
+        let posed: Vec<Point3> = bounds.corners().iter().map(|&corner| matrix.transform_point(corner)).collect();
+
+        // between poses a corner moves along an arc, straying at most r (1 - cos(swept / 2))
+        // from the chord joining where it was taken
+        let radius = posed.iter().map(|&corner| (corner - center).length()).fold(0.0, f64::max);
+        let pad = radius * (1.0 - f64::cos(0.5 * swept));
+        let extent = Vec3::new(pad, pad, pad);
+        for corner in posed {
+            corners.push(corner - extent);
+            corners.push(corner + extent);
+        }
+    }
+
+    AABB::enclosing(&corners)
+}
+
+fn solid_angle_scale(matrix: &Mat4, object_direction: Vec3) -> f64 {
+    matrix.transform_vector(object_direction).length().powi(3) / f64::abs(matrix.determinant3())
+}
+
+// uneven scaling bends normals by different amounts, so emission cones only survive rotations
+// and uniform scales
+fn is_conformal(matrix: &Mat4) -> bool {
+    let columns = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)].map(|axis| matrix.transform_vector(axis));
+    let scale = columns[0].length_squared();
+
+    [(0, 1), (0, 2), (1, 2)].iter().all(|&(i, j)| f64::abs(dot(columns[i], columns[j])) < 1e-9 * scale)
+        && f64::abs(columns[1].length_squared() - scale) < 1e-9 * scale
+        && f64::abs(columns[2].length_squared() - scale) < 1e-9 * scale
+}
+
+// Directions sampled in object space are carried into the world by the matrix, which
+// stretches solid angles: a unit direction d maps with density scaled by |M d|^3 / |det M|.
+struct TransformedLight {
+    light: Arc<dyn Light>,
+    placement: Placement
+}
+
+impl Light for TransformedLight {
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let (matrix, inverse) = self.placement.at(time);
+        let light_sample = self.light.sample_li(inverse.transform_point(point), time)?;
+        let direction = matrix.transform_vector(light_sample.direction);
+        let length = direction.length();
+
+        Some(LightSample {
+            direction: direction / length,
+            radiance: light_sample.radiance,
+            pdf: light_sample.pdf * solid_angle_scale(&matrix, light_sample.direction),
+            distance: light_sample.distance * length
+        })
+    }
+
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let (matrix, inverse) = self.placement.at(time);
+        let object_direction = unit_vector(inverse.transform_vector(direction));
+        let pdf = self.light.pdf_li(inverse.transform_point(point), object_direction, time);
+
+        pdf * solid_angle_scale(&matrix, object_direction)
+    }
+
+    fn is_delta(&self) -> bool {
+        self.light.is_delta()
+    }
+
+    // only a heuristic for choosing between lights, not the true power: areas grow with the square
+    // of the scale, which is taken as the same along every axis, and it is averaged over the motion
+    fn power(&self) -> f64 {
+        let poses = self.placement.poses();
+        let scale: f64 = poses.iter().map(|(matrix, _, _)| f64::powf(f64::abs(matrix.determinant3()), 2.0 / 3.0)).sum();
+
+        self.light.power() * scale / poses.len() as f64
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let light_bounds = self.light.bounds()?;
+        let poses = self.placement.poses();
+
+        let (_, inverse, _) = poses[0];
+        let fixed_cone = poses.iter().all(|(matrix, _, swept)| *swept == 0.0 && is_conformal(matrix));
+
+        Some(LightBounds {
+            bounds: swept_bounds(&light_bounds.bounds, &poses),
+            direction: unit_vector(inverse.transpose().transform_vector(light_bounds.direction)),
+            cos_theta_o: if fixed_cone {light_bounds.cos_theta_o} else {-1.0},
+            phi: self.power(),
+            ..light_bounds
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{material::Empty, quad::Quad, sphere::Sphere};
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        (a - b).length() < 1e-9
+    }
+
+    // out to x = 2 quickly and slowly back again
+    fn out_and_back() -> Vec<Keyframe> {
+        let (upright, unit) = (Quaternion::identity(), Vec3::new(1.0, 1.0, 1.0));
+        vec![
+            Keyframe::new(0.0, Vec3::new(0.0, 0.0, 0.0), upright, unit),
+            Keyframe::new(0.2, Vec3::new(2.0, 0.0, 0.0), upright, unit),
+            Keyframe::new(1.0, Vec3::new(0.0, 4.0, 0.0), Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 90.0), Vec3::new(2.0, 2.0, 2.0))
+        ]
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_uneven_scaling() {
+        // a unit sphere squashed into an ellipsoid twice as wide along x as it is tall
+        let sphere = Arc::new(Sphere::new(Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0), 0.0), Arc::new(Empty::new()), 1.0));
+        let ellipsoid = Transform::scale(sphere, Vec3::new(2.0, 1.0, 1.0));
+
+        // x^2 / 4 + y^2 = 1 has gradient (x / 2, 2y), so (sqrt 2, 1 / sqrt 2) has normal along (1, 2)
+        let target = Point3::new(f64::sqrt(2.0), 1.0 / f64::sqrt(2.0), 0.0);
+        let ray = Ray::new(target + 3.0 * Vec3::new(1.0, 2.0, 0.0), -Vec3::new(1.0, 2.0, 0.0), 0.0);
+        let hit_record = ellipsoid.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert!(close(hit_record.p, target));
+        assert!(close(hit_record.normal, unit_vector(Vec3::new(1.0, 2.0, 0.0))));
+    }
+
+    #[test]
+    fn interpolates_between_unevenly_spaced_keyframes() {
+        let keyframes = out_and_back();
+
+        assert!(close(interpolate(&keyframes, 0.1).translation, Vec3::new(1.0, 0.0, 0.0)));
+        assert!(close(interpolate(&keyframes, 0.6).translation, Vec3::new(1.0, 2.0, 0.0)));
+        assert!(close(interpolate(&keyframes, 0.6).scale, Vec3::new(1.5, 1.5, 1.5)));
+        assert!(f64::abs(Quaternion::angle_between(interpolate(&keyframes, 0.6).rotation, Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 45.0))) < 1e-6);
+
+        // held still outside the keyframes
+        assert!(close(interpolate(&keyframes, -1.0).translation, Vec3::new(0.0, 0.0, 0.0)));
+        assert!(close(interpolate(&keyframes, 2.0).translation, Vec3::new(0.0, 4.0, 0.0)));
+    }
+
+    #[test]
+    fn bounds_cover_the_whole_motion() {
+        let unit_box = Arc::new(Quad::get_box(Point3::new(-0.5, -0.5, -0.5), Point3::new(0.5, 0.5, 0.5), Arc::new(Empty::new())));
+        let object_bounds = unit_box.bounding_box().unwrap();
+        let animated = AnimatedTransform::new(unit_box, out_and_back());
+        let bounds = animated.bounding_box().unwrap();
+
+        for i in 0..=1000 {
+            let (matrix, _) = interpolate(&animated.keyframes, i as f64 / 1000.0).matrices();
+            for corner in object_bounds.corners() {
+                assert!(bounds.contains(matrix.transform_point(corner)), "corner at time {} left the bounds", i as f64 / 1000.0);
+            }
+        }
+    }
+}
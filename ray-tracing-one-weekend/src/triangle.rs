@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use crate::{aabb::AABB, common::random_double, hittable::{HitRecord, Hittable}, light::{emitted_power, sample_emitter, Light, LightList, LightSample}, light_bvh::LightBounds, material::Material, ray::Ray, vec3::{cross, dot, unit_vector, Point3, Vec3}};
+
+pub struct Triangle {
+    a: Point3,
+    ab: Vec3,
+    ac: Vec3,
+    normal: Vec3,
+    area: f64,
+    mat: Arc<dyn Material>
+}
+
+impl Triangle {
+    // the normal faces the side from which a, b and c run counter clockwise
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Arc<dyn Material>) -> Self {
+        let ab = b - a;
+        let ac = c - a;
+        let n = cross(ab, ac);
+
+        Triangle {
+            a,
+            ab,
+            ac,
+            normal: unit_vector(n),
+            area: 0.5 * n.length(),
+            mat
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore, with the barycentric coordinates of b and c as the hit's u and v
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let p = cross(ray.direction(), self.ac);
+        let det = dot(self.ab, p);
+
+        // ray parallel to the triangle
+        if f64::abs(det) < 1e-12 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = ray.origin() - self.a;
+        let u = dot(s, p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = cross(s, self.ab);
+        let v = dot(ray.direction(), q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = dot(self.ac, q) * inv_det;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            t,
+            p: ray.at(t),
+            mat: self.mat.clone(),
+            normal: Default::default(),
+            front_face: Default::default(),
+            u,
+            v,
+        };
+
+        rec.set_face_normal(ray, self.normal);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<AABB> {
+        Some(AABB::from_sub_aabbs(
+            AABB::from_points(self.a, self.a + self.ab),
+            AABB::from_points(self.a, self.a + self.ac),
+        ))
+    }
+
+    fn collect_lights(self: Arc<Self>, lights: &mut LightList) {
+        if self.mat.is_emissive() {
+            lights.add(self);
+        }
+    }
+}
+
+impl Light for Triangle {
+    // samples a point uniformly over the triangle's area
+    fn sample_li(&self, point: Point3, time: f64) -> Option<LightSample> {
+        let sqrt_r1 = f64::sqrt(random_double());
+        let r2 = random_double();
+        let p = self.a + (1.0 - r2) * sqrt_r1 * self.ab + r2 * sqrt_r1 * self.ac;
+        let direction = p - point;
+
+        sample_emitter(self, point, direction, time, self.pdf_li(point, direction, time))
+    }
+
+    fn pdf_li(&self, point: Point3, direction: Vec3, time: f64) -> f64 {
+        let rec = match self.hit(&Ray::new(point, direction, time), 0.001, f64::INFINITY) {
+            Some(rec) => rec,
+            None => return 0.0
+        };
+
+        let distance_squared = rec.t * rec.t * direction.length_squared();
+        let cosine = f64::abs(dot(direction, rec.normal) / direction.length());
+
+        distance_squared / (cosine * self.area)
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+
+    fn power(&self) -> f64 {
+        let spots: Vec<(Point3, Vec3, f64, f64)> = [(1.0 / 3.0, 1.0 / 3.0), (1.0 / 6.0, 1.0 / 6.0), (2.0 / 3.0, 1.0 / 6.0), (1.0 / 6.0, 2.0 / 3.0)]
+            .into_iter()
+            .map(|(u, v)| (self.a + u * self.ab + v * self.ac, self.normal, u, v))
+            .collect();
+
+        emitted_power(&self.mat, self.area, &spots)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let bounds = self.bounding_box()?;
+
+        let spread = self.mat.emission_spread();
+        Some(LightBounds::new(bounds, self.normal, 1.0, spread.cos_theta_e, self.power(), spread.two_sided))
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs;
+
+use crate::{color::Color, heterogeneous_medium::DensityField, texture::Texture, vec3::Point3};
+
+const MAGIC: &[u8; 4] = b"VOXG";
+const HEADER_SIZE: usize = 4 + 3 * 4 + 6 * 4;
+
+// A dense grid of cell-centred values stretched over an axis aligned box.
+//
+// The file layout is little endian: the magic "VOXG", the resolution as three u32s,
+// the bounds as six f32s (min x, y, z then max x, y, z) and then one f32 per cell
+// with x varying fastest, then y, then z.
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    min: Point3,
+    max: Point3,
+    data: Vec<f64>,
+    max_value: f64
+}
+
+impl VoxelGrid {
+    pub fn new(grid_file: &str) -> Result<VoxelGrid, String> {
+        let bytes = fs::read(grid_file).map_err(|e| format!("couldn't read voxel grid {}: {}", grid_file, e))?;
+        VoxelGrid::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<VoxelGrid, String> {
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC {
+            return Err("not a voxel grid file".to_string());
+        }
+
+        let read_u32 = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize;
+        let read_f32 = |offset: usize| f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as f64;
+
+        let resolution = [read_u32(4), read_u32(8), read_u32(12)];
+        if resolution.contains(&0) {
+            return Err(format!("voxel grid resolution {:?} has an empty axis", resolution));
+        }
+        let min = Point3::new(read_f32(16), read_f32(20), read_f32(24));
+        let max = Point3::new(read_f32(28), read_f32(32), read_f32(36));
+
+        let cells = resolution[0].checked_mul(resolution[1]).and_then(|c| c.checked_mul(resolution[2]));
+        let size = cells.and_then(|c| c.checked_mul(4)).and_then(|data_size| data_size.checked_add(HEADER_SIZE));
+        let cells = match (cells, size) {
+            (Some(cells), Some(size)) if bytes.len() >= size => cells,
+            _ => return Err(format!("voxel grid is too short for its {:?} cells", resolution))
+        };
+        let data = (0..cells).map(|i| read_f32(HEADER_SIZE + 4 * i)).collect();
+
+        Ok(VoxelGrid::from_data(resolution, min, max, data))
+    }
+
+    pub fn from_data(resolution: [usize; 3], min: Point3, max: Point3, data: Vec<f64>) -> VoxelGrid {
+        assert!(!resolution.contains(&0), "voxel grid should have at least one cell along each axis");
+        assert_eq!(data.len(), resolution[0] * resolution[1] * resolution[2], "voxel grid should contain every cell");
+        let max_value = data.iter().cloned().fold(0.0, f64::max);
+
+        VoxelGrid {
+            resolution,
+            min,
+            max,
+            data,
+            max_value
+        }
+    }
+
+    fn cell(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[x + self.resolution[0] * (y + self.resolution[1] * z)]
+    }
+
+    // trilinearly interpolated value at a world space point, zero outside the bounds
+    pub fn value(&self, p: &Point3) -> f64 {
+        let extent = self.max - self.min;
+        let local = [
+            (p.x() - self.min.x()) / extent.x(),
+            (p.y() - self.min.y()) / extent.y(),
+            (p.z() - self.min.z()) / extent.z(),
+        ];
+        if local.iter().any(|l| !(0.0..=1.0).contains(l)) {
+            return 0.0;
+        }
+
+        // lower corner of the surrounding cells and the fraction of the way to the upper ones
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut fraction = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.resolution[axis];
+            let g = f64::clamp(local[axis] * n as f64 - 0.5, 0.0, (n - 1) as f64);
+            lower[axis] = g as usize;
+            upper[axis] = usize::min(lower[axis] + 1, n - 1);
+            fraction[axis] = g - lower[axis] as f64;
+        }
+
+        let mut accum = 0.0;
+        for (dx, x) in [lower[0], upper[0]].into_iter().enumerate() {
+            for (dy, y) in [lower[1], upper[1]].into_iter().enumerate() {
+                for (dz, z) in [lower[2], upper[2]].into_iter().enumerate() {
+                    let weight_x = if dx == 0 {1.0 - fraction[0]} else {fraction[0]};
+                    let weight_y = if dy == 0 {1.0 - fraction[1]} else {fraction[1]};
+                    let weight_z = if dz == 0 {1.0 - fraction[2]} else {fraction[2]};
+                    accum += weight_x * weight_y * weight_z * self.cell(x, y, z);
+                }
+            }
+        }
+
+        accum
+    }
+}
+
+impl DensityField for VoxelGrid {
+    fn density(&self, p: &Point3) -> f64 {
+        self.value(p)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_value
+    }
+}
+
+impl Texture for VoxelGrid {
+    fn get_color(&self, _u: f64, _v: f64, point: &Point3) -> Color {
+        let value = self.value(point);
+        Color::new(value, value, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_file(resolution: [u32; 3], cells: usize) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for n in resolution {
+            bytes.extend(n.to_le_bytes());
+        }
+        for bound in [0.0f32, 0.0, 0.0, 1.0, 1.0, 1.0] {
+            bytes.extend(bound.to_le_bytes());
+        }
+        for _ in 0..cells {
+            bytes.extend(1.0f32.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn rejects_empty_axes_and_short_files() {
+        assert!(VoxelGrid::parse(&grid_file([2, 0, 2], 0)).is_err());
+        assert!(VoxelGrid::parse(&grid_file([2, 2, 2], 7)).is_err());
+        assert!(VoxelGrid::parse(b"VOX").is_err());
+
+        let grid = VoxelGrid::parse(&grid_file([2, 2, 2], 8)).unwrap();
+        assert_eq!(grid.value(&Point3::new(0.5, 0.5, 0.5)), 1.0);
+    }
+}